@@ -0,0 +1,266 @@
+use async_trait::async_trait;
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    marker::PhantomData,
+};
+
+use crate::{Actor, ActorContext, Message, handler::catch_unwind};
+
+/// Handles `M` for actor `A` while a `Behavior` it's part of is active.
+#[async_trait]
+pub trait BehaviorHandler<A: Actor, M: Message>: Send + Sync + 'static {
+    async fn handle(&mut self, actor: &mut A, msg: M, ctx: &mut ActorContext) -> M::Response;
+}
+
+/// Table of handlers that take over from the actor's `Handler` impls while
+/// it's active, see `ActorContext::become_behavior`. Messages without a
+/// handler in the table still go to the actor's `Handler` impls.
+pub struct Behavior<A: Actor> {
+    handlers: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    _actor: PhantomData<fn() -> A>,
+}
+
+impl<A: Actor> Behavior<A> {
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            _actor: PhantomData,
+        }
+    }
+
+    /// Handles `M` with `handler`, replacing an earlier handler of `M`.
+    pub fn on<M: Message>(mut self, handler: impl BehaviorHandler<A, M>) -> Self {
+        let handler: Box<dyn BehaviorHandler<A, M>> = Box::new(handler);
+        self.handlers.insert(TypeId::of::<M>(), Box::new(handler));
+        self
+    }
+
+    pub fn handles<M: Message>(&self) -> bool {
+        self.handlers.contains_key(&TypeId::of::<M>())
+    }
+
+    fn handler<M: Message>(&mut self) -> Option<&mut Box<dyn BehaviorHandler<A, M>>> {
+        self.handlers.get_mut(&TypeId::of::<M>())?.downcast_mut()
+    }
+}
+
+impl<A: Actor> Default for Behavior<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Actor> std::fmt::Debug for Behavior<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Behavior")
+            .field("handlers", &self.handlers.len())
+            .finish()
+    }
+}
+
+/// Behaviour stack of an actor. The active behaviour is taken out while it
+/// handles a message, leaving an empty slot, so the handler can change the
+/// stack through the context.
+#[derive(Debug, Default)]
+pub(crate) struct Behaviors {
+    stack: Vec<Option<Box<dyn Any + Send + Sync>>>,
+}
+
+impl Behaviors {
+    pub(crate) fn push<A: Actor>(&mut self, behavior: Behavior<A>) {
+        self.stack.push(Some(Box::new(behavior)));
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.stack.pop();
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.stack.clear();
+    }
+}
+
+/// Lets the active behaviour handle `msg` if it has a handler for it,
+/// otherwise hands `msg` back.
+pub(crate) async fn handle<A: Actor, M: Message>(
+    actor: &mut A,
+    msg: M,
+    ctx: &mut ActorContext,
+) -> Result<M::Response, M> {
+    let index = ctx.behaviors.stack.len().wrapping_sub(1);
+    let active = ctx
+        .behaviors
+        .stack
+        .last_mut()
+        .and_then(|slot| slot.take_if(|behavior| active_handles::<A, M>(behavior)));
+    let Some(mut active) = active else {
+        return Err(msg);
+    };
+
+    let behavior = active.downcast_mut::<Behavior<A>>().unwrap();
+    let handler = behavior.handler::<M>().unwrap();
+    let response = catch_unwind(handler.handle(actor, msg, ctx)).await;
+
+    // the slot is gone if the handler called `unbecome`, a panicking handler
+    // must not leave it empty for an actor that is resumed
+    if let Some(slot) = ctx.behaviors.stack.get_mut(index)
+        && slot.is_none()
+    {
+        *slot = Some(active);
+    }
+
+    match response {
+        Ok(response) => Ok(response),
+        Err(payload) => std::panic::resume_unwind(payload),
+    }
+}
+
+fn active_handles<A: Actor, M: Message>(behavior: &mut Box<dyn Any + Send + Sync>) -> bool {
+    behavior
+        .downcast_ref::<Behavior<A>>()
+        .is_some_and(Behavior::handles::<M>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Directive, SupervisorStrategy, prelude::*};
+
+    #[derive(Default)]
+    struct Session {
+        user: Option<String>,
+    }
+
+    #[async_trait]
+    impl Actor for Session {}
+
+    struct Login(&'static str);
+    impl Message for Login {
+        type Response = ();
+    }
+
+    struct Logout;
+    impl Message for Logout {
+        type Response = ();
+    }
+
+    struct Whoami;
+    impl Message for Whoami {
+        type Response = String;
+    }
+
+    struct Crash;
+    impl Message for Crash {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Login> for Session {
+        async fn handle(&mut self, msg: Login, ctx: &mut ActorContext) {
+            self.user = Some(msg.0.into());
+            ctx.become_behavior(
+                Behavior::new()
+                    .on::<Whoami>(LoggedIn)
+                    .on::<Logout>(LoggedIn)
+                    .on::<Crash>(LoggedIn),
+            );
+        }
+    }
+
+    #[async_trait]
+    impl Handler<Logout> for Session {
+        async fn handle(&mut self, _msg: Logout, _ctx: &mut ActorContext) {}
+    }
+
+    #[async_trait]
+    impl Handler<Crash> for Session {
+        async fn handle(&mut self, _msg: Crash, _ctx: &mut ActorContext) {}
+    }
+
+    #[async_trait]
+    impl Handler<Whoami> for Session {
+        async fn handle(&mut self, _msg: Whoami, _ctx: &mut ActorContext) -> String {
+            "anonymous".into()
+        }
+    }
+
+    struct LoggedIn;
+
+    #[async_trait]
+    impl BehaviorHandler<Session, Whoami> for LoggedIn {
+        async fn handle(
+            &mut self,
+            actor: &mut Session,
+            _msg: Whoami,
+            _ctx: &mut ActorContext,
+        ) -> String {
+            actor.user.clone().unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl BehaviorHandler<Session, Logout> for LoggedIn {
+        async fn handle(&mut self, actor: &mut Session, _msg: Logout, ctx: &mut ActorContext) {
+            actor.user = None;
+            ctx.unbecome();
+        }
+    }
+
+    #[async_trait]
+    impl BehaviorHandler<Session, Crash> for LoggedIn {
+        async fn handle(&mut self, _actor: &mut Session, _msg: Crash, _ctx: &mut ActorContext) {
+            panic!("crash");
+        }
+    }
+
+    struct Guardian;
+    #[async_trait]
+    impl Actor for Guardian {}
+
+    struct SpawnSession;
+    impl Message for SpawnSession {
+        type Response = ActorRef<Session>;
+    }
+
+    #[async_trait]
+    impl Handler<SpawnSession> for Guardian {
+        async fn handle(
+            &mut self,
+            _msg: SpawnSession,
+            ctx: &mut ActorContext,
+        ) -> ActorRef<Session> {
+            ctx.spawn("session", Session::default, 10).await.unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn behaviors_override_handlers_until_unbecome() {
+        let system = ActorSystem::new();
+        let session = system.spawn("session", Session::default, 10).await.unwrap();
+
+        assert_eq!(session.ask(Whoami).await.unwrap(), "anonymous");
+        session.ask(Login("alice")).await.unwrap();
+        assert_eq!(session.ask(Whoami).await.unwrap(), "alice");
+        session.ask(Logout).await.unwrap();
+        assert_eq!(session.ask(Whoami).await.unwrap(), "anonymous");
+    }
+
+    #[tokio::test]
+    async fn resumed_actor_keeps_behavior_after_panic() {
+        let system = ActorSystem::new();
+        let strategy = SupervisorStrategy::default().with_decider(|_| Directive::Resume);
+        let props = ActorProps::new(
+            || Guardian,
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(DefaultMailbox::new(10)),
+        )
+        .with_supervisor_strategy(strategy);
+        let guardian = system.spawn_props("guardian", props).await.unwrap();
+        let session = guardian.ask(SpawnSession).await.unwrap();
+
+        session.ask(Login("alice")).await.unwrap();
+        assert!(session.ask(Crash).await.is_err());
+        assert_eq!(session.ask(Whoami).await.unwrap(), "alice");
+    }
+}
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use std::time::Duration;
 
 use actor_rs::prelude::*;
 
@@ -59,4 +60,6 @@ async fn main() {
         // sys.stop_actor(a1.path()).await;
         // sys.stop_actor(a2.path()).await;
     }
+
+    sys.shutdown(Duration::from_secs(5)).await;
 }
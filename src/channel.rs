@@ -0,0 +1,353 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{Arc, Mutex, Weak},
+};
+
+use tokio::sync::{Notify, Semaphore, TryAcquireError};
+
+/// What a bounded mailbox does with a message sent while it's full.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OverflowStrategy {
+    /// The sender waits until there is room.
+    #[default]
+    Backpressure,
+    /// The message is dropped.
+    DropNewest,
+    /// The oldest queued message is dropped to make room.
+    DropOldest,
+    /// Sending fails with `ActorError::MailboxFull`.
+    Fail,
+}
+
+#[derive(Debug)]
+pub(crate) enum SendError<T> {
+    Closed(T),
+    Full(T),
+}
+
+/// Creates the queue behind a mailbox, unbounded if `capacity` is `None`.
+/// Custom `Mailbox` implementations use it to create their `Sender`.
+///
+/// Panics if `capacity` is `Some(0)`, such a queue could never hold a message.
+pub fn channel<T>(capacity: Option<usize>, overflow: OverflowStrategy) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity != Some(0), "mailbox capacity must be at least 1");
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            closed: false,
+            senders: 1,
+        }),
+        // every queued message holds one permit
+        permits: capacity.map(Semaphore::new),
+        overflow,
+        ready: Notify::new(),
+    });
+
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    permits: Option<Semaphore>,
+    overflow: OverflowStrategy,
+    /// Wakes the receiver when a message arrives or the last sender is gone.
+    ready: Notify,
+}
+
+struct State<T> {
+    queue: VecDeque<Queued<T>>,
+    closed: bool,
+    senders: usize,
+}
+
+struct Queued<T> {
+    msg: T,
+    /// Whether the message took a permit, forced messages don't.
+    permit: bool,
+    forced: bool,
+}
+
+impl<T> Shared<T> {
+    fn state(&self) -> std::sync::MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    /// Queues `msg`, waiting for room if the queue is full and applies
+    /// backpressure. Returns the message dropped to make room, if any.
+    pub(crate) async fn send(&self, msg: T) -> Result<Option<T>, SendError<T>> {
+        if let (Some(permits), OverflowStrategy::Backpressure) =
+            (&self.shared.permits, self.shared.overflow)
+        {
+            let Ok(permit) = permits.acquire().await else {
+                return Err(SendError::Closed(msg));
+            };
+            permit.forget();
+            return self.push(msg, true, false).map(|()| None);
+        }
+
+        self.try_send(msg)
+    }
+
+    /// Queues `msg` without waiting, a full queue applying backpressure fails.
+    /// Returns the message dropped to make room, if any.
+    pub(crate) fn try_send(&self, msg: T) -> Result<Option<T>, SendError<T>> {
+        let Some(permits) = &self.shared.permits else {
+            return self.push(msg, false, false).map(|()| None);
+        };
+
+        let mut state = self.shared.state();
+        if state.closed {
+            return Err(SendError::Closed(msg));
+        }
+        let mut dropped = None;
+        match permits.try_acquire() {
+            Ok(permit) => permit.forget(),
+            Err(TryAcquireError::Closed) => return Err(SendError::Closed(msg)),
+            Err(TryAcquireError::NoPermits) => match self.shared.overflow {
+                OverflowStrategy::DropNewest => return Ok(Some(msg)),
+                OverflowStrategy::DropOldest => {
+                    // the permit of the dropped message is taken over
+                    let Some(oldest) = state.queue.iter().position(|queued| queued.permit) else {
+                        return Err(SendError::Full(msg));
+                    };
+                    dropped = state.queue.remove(oldest).map(|queued| queued.msg);
+                }
+                _ => return Err(SendError::Full(msg)),
+            },
+        }
+        state.queue.push_back(Queued {
+            msg,
+            permit: true,
+            forced: false,
+        });
+        drop(state);
+
+        self.shared.ready.notify_one();
+        Ok(dropped)
+    }
+
+    /// Queues `msg` regardless of the capacity, it's never dropped to make
+    /// room either.
+    pub(crate) fn force_send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.push(msg, false, true)
+    }
+
+    fn push(&self, msg: T, permit: bool, forced: bool) -> Result<(), SendError<T>> {
+        let mut state = self.shared.state();
+        if state.closed {
+            return Err(SendError::Closed(msg));
+        }
+        state.queue.push_back(Queued {
+            msg,
+            permit,
+            forced,
+        });
+        drop(state);
+
+        self.shared.ready.notify_one();
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shared.state().closed
+    }
+
+    pub fn downgrade(&self) -> WeakSender<T> {
+        WeakSender {
+            shared: Arc::downgrade(&self.shared),
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.ready.notify_one();
+        }
+    }
+}
+
+impl<T> Debug for Sender<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// Sender that doesn't keep the queue open.
+pub struct WeakSender<T> {
+    shared: Weak<Shared<T>>,
+}
+
+impl<T> WeakSender<T> {
+    pub fn upgrade(&self) -> Option<Sender<T>> {
+        let shared = self.shared.upgrade()?;
+        let mut state = shared.state();
+        if state.senders == 0 {
+            return None;
+        }
+        state.senders += 1;
+        drop(state);
+
+        Some(Sender { shared })
+    }
+}
+
+impl<T> Clone for WeakSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Debug for WeakSender<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeakSender").finish_non_exhaustive()
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Waits for the next message, `None` once the queue is empty and closed
+    /// or all senders are gone.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            {
+                let mut state = self.shared.state();
+                if let Some(msg) = self.pop(&mut state) {
+                    return Some(msg);
+                }
+                if state.closed || state.senders == 0 {
+                    return None;
+                }
+            }
+            self.shared.ready.notified().await;
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<T> {
+        let mut state = self.shared.state();
+        self.pop(&mut state)
+    }
+
+    /// Takes the oldest message sent with `Sender::force_send`, skipping the
+    /// ones queued before it.
+    pub(crate) fn try_recv_forced(&mut self) -> Option<T> {
+        let mut state = self.shared.state();
+        let index = state.queue.iter().position(|queued| queued.forced)?;
+        state.queue.remove(index).map(|queued| queued.msg)
+    }
+
+    /// Stops accepting messages, queued ones can still be received.
+    pub fn close(&mut self) {
+        self.shared.state().closed = true;
+        if let Some(permits) = &self.shared.permits {
+            permits.close();
+        }
+    }
+
+    fn pop(&self, state: &mut State<T>) -> Option<T> {
+        let queued = state.queue.pop_front()?;
+        if let Some(permits) = &self.shared.permits
+            && queued.permit
+        {
+            permits.add_permits(1);
+        }
+        Some(queued.msg)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl<T> Debug for Receiver<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("queued", &self.shared.state().queue.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(receiver: &mut Receiver<u32>) -> Vec<u32> {
+        std::iter::from_fn(|| receiver.try_recv()).collect()
+    }
+
+    #[test]
+    #[should_panic(expected = "mailbox capacity must be at least 1")]
+    fn zero_capacity_is_rejected() {
+        let _ = channel::<u32>(Some(0), OverflowStrategy::Backpressure);
+    }
+
+    #[tokio::test]
+    async fn overflow_strategies() {
+        let (sender, mut receiver) = channel(Some(2), OverflowStrategy::DropNewest);
+        let mut dropped = Vec::new();
+        for i in 0..4 {
+            dropped.extend(sender.send(i).await.unwrap());
+        }
+        assert_eq!(dropped, vec![2, 3]);
+        assert_eq!(drain(&mut receiver), vec![0, 1]);
+
+        let (sender, mut receiver) = channel(Some(2), OverflowStrategy::DropOldest);
+        sender.send(0).await.unwrap();
+        sender.force_send(1).unwrap();
+        let mut dropped = Vec::new();
+        for i in 2..5 {
+            dropped.extend(sender.send(i).await.unwrap());
+        }
+        // forced messages are neither dropped nor count against the capacity
+        assert_eq!(dropped, vec![0, 2]);
+        assert_eq!(drain(&mut receiver), vec![1, 3, 4]);
+
+        let (sender, mut receiver) = channel(Some(2), OverflowStrategy::Fail);
+        sender.send(0).await.unwrap();
+        sender.send(1).await.unwrap();
+        assert!(matches!(sender.send(2).await, Err(SendError::Full(2))));
+        assert_eq!(drain(&mut receiver), vec![0, 1]);
+        sender.send(3).await.unwrap();
+
+        let (sender, mut receiver) = channel(Some(1), OverflowStrategy::Backpressure);
+        sender.send(0).await.unwrap();
+        assert!(matches!(sender.try_send(1), Err(SendError::Full(1))));
+        let blocked = tokio::spawn(async move { sender.send(2).await.is_ok() });
+        assert_eq!(receiver.recv().await, Some(0));
+        assert!(blocked.await.unwrap());
+        assert_eq!(receiver.recv().await, Some(2));
+        // all senders are gone
+        assert_eq!(receiver.recv().await, None);
+    }
+}
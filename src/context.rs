@@ -1,34 +1,144 @@
-use std::marker::PhantomData;
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::sync::oneshot;
 
 use crate::{
-    Actor, ActorPath, ActorProps, ActorRef, ActorSpawner, ActorSystem, DefaultActorSpawner,
-    DefaultMailbox, Mailbox, Result,
+    Actor, ActorError, ActorPath, ActorProps, ActorRef, ActorSpawner, ActorSystem, Behavior,
+    Directive, Handler, Mailbox, Message, Result, ServiceKey, SupervisorStrategy, Terminated,
+    Throughput, TimerMode, UntypedActorRef, behavior::Behaviors, interceptor::Interceptors,
+    selection::ActorSelection, supervisor::RestartStats, system::SystemMessage, timer::Timers,
 };
 
 #[derive(Debug)]
 pub struct ActorContext {
     pub path: ActorPath,
     pub system: ActorSystem,
+    pub(crate) myself: UntypedActorRef,
+    pub(crate) parent: Option<UntypedActorRef>,
+    pub(crate) supervisor_strategy: SupervisorStrategy,
+    pub(crate) restart_stats: HashMap<UntypedActorRef, RestartStats>,
+    pub(crate) watchers: Watchers,
+    /// Path and incarnation of the watched actors.
+    pub(crate) watching: HashSet<(ActorPath, u64)>,
+    pub(crate) receive_timeout: Option<Duration>,
+    pub(crate) stop_requests: Vec<oneshot::Sender<()>>,
+    /// Set once the actor has to stop whatever `Actor::stopping` says,
+    /// because its parent is stopping.
+    pub(crate) stop_forced: bool,
+    pub(crate) timers: Timers,
+    pub(crate) behaviors: Behaviors,
+    pub(crate) stash_capacity: usize,
+    pub(crate) throughput: Throughput,
+    pub(crate) interceptors: Interceptors,
+    /// Number of stashed messages, kept up to date by the mailbox.
+    pub(crate) stash_size: usize,
+    /// Message handed to `stash` by the handler that is running.
+    pub(crate) stashing: Option<Box<dyn Any + Send + Sync>>,
+    /// Type of the message being handled, the only one that can be stashed.
+    pub(crate) handling: Option<TypeId>,
+    pub(crate) unstash_requested: usize,
     pub(crate) _private: PhantomData<()>,
 }
 
+/// Actors watching an actor. Shared with its registry entry, so watchers
+/// of actors killed by `ActorSystem::shutdown` are notified as well.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Watchers(Arc<Mutex<HashSet<UntypedActorRef>>>);
+
+impl Watchers {
+    pub(crate) fn insert(&self, watcher: UntypedActorRef) {
+        self.0.lock().unwrap().insert(watcher);
+    }
+
+    pub(crate) fn remove(&self, watcher: &UntypedActorRef) {
+        self.0.lock().unwrap().remove(watcher);
+    }
+
+    /// Sends `terminated` to every watcher, each is notified only once.
+    pub(crate) async fn notify(&self, terminated: Terminated) {
+        let watchers: Vec<_> = self.0.lock().unwrap().drain().collect();
+        for watcher in watchers {
+            let msg = SystemMessage::Terminated(terminated.clone());
+            let _ = watcher.send_system(msg).await;
+        }
+    }
+}
+
 impl ActorContext {
-    pub async fn spawn<A: Actor, F: Fn() -> A>(
+    pub(crate) fn new(
+        path: ActorPath,
+        system: ActorSystem,
+        myself: UntypedActorRef,
+        parent: Option<UntypedActorRef>,
+        supervisor_strategy: SupervisorStrategy,
+        stash_capacity: usize,
+        throughput: Throughput,
+    ) -> Self {
+        Self {
+            path,
+            system,
+            myself,
+            parent,
+            supervisor_strategy,
+            restart_stats: HashMap::new(),
+            watchers: Watchers::default(),
+            watching: HashSet::new(),
+            receive_timeout: None,
+            stop_requests: Vec::new(),
+            stop_forced: false,
+            timers: Timers::default(),
+            behaviors: Behaviors::default(),
+            stash_capacity,
+            throughput,
+            interceptors: Interceptors::default(),
+            stash_size: 0,
+            stashing: None,
+            handling: None,
+            unstash_requested: 0,
+            _private: PhantomData,
+        }
+    }
+
+    pub async fn spawn<A: Actor, F: Fn() -> A + Send + Sync + 'static>(
         &self,
         name: &str,
         actor_fn: F,
         buffer: usize,
     ) -> Result<ActorRef<A>> {
-        let props = ActorProps::new(
-            actor_fn,
-            || Box::new(DefaultActorSpawner::new()),
-            || Box::new(DefaultMailbox::<A>::new(buffer)),
-        );
-
-        let child = self.path.join(name);
+        let props = self.system.buffered_props(actor_fn, buffer);
+        let child = self.path.child(name)?;
         self.system.spawn_path(child, props).await
     }
 
+    /// Spawns a child with a generated name, see `ActorSystem::spawn_anonymous`.
+    pub async fn spawn_anonymous<A: Actor, F: Fn() -> A + Send + Sync + 'static>(
+        &self,
+        actor_fn: F,
+        buffer: usize,
+    ) -> Result<ActorRef<A>> {
+        let props = self.system.buffered_props(actor_fn, buffer);
+        self.system.spawn_generated(&self.path, None, props).await
+    }
+
+    /// Spawns a child with a generated name starting with `prefix`.
+    pub async fn spawn_with_prefix<A: Actor, F: Fn() -> A + Send + Sync + 'static>(
+        &self,
+        prefix: &str,
+        actor_fn: F,
+        buffer: usize,
+    ) -> Result<ActorRef<A>> {
+        let props = self.system.buffered_props(actor_fn, buffer);
+        self.system
+            .spawn_generated(&self.path, Some(prefix), props)
+            .await
+    }
+
     pub async fn spawn_props<A, F, S, M>(
         &self,
         name: &str,
@@ -36,21 +146,272 @@ impl ActorContext {
     ) -> Result<ActorRef<A>>
     where
         A: Actor,
-        F: Fn() -> A,
+        F: Fn() -> A + Send + Sync + 'static,
         S: Fn() -> Box<dyn ActorSpawner<A>>,
         M: Fn() -> Box<dyn Mailbox<A>>,
     {
-        let child = self.path.join(name);
+        let child = self.path.child(name)?;
         self.system.spawn_path(child, props).await
     }
 
+    /// Reference to this actor, usually called as `ctx.myself::<Self>()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `A` is not the type of this actor.
+    pub fn myself<A: Actor>(&self) -> ActorRef<A> {
+        self.myself.downcast::<A>().unwrap_or_else(|| {
+            panic!(
+                "{} is not the type of actor {}",
+                std::any::type_name::<A>(),
+                self.path
+            )
+        })
+    }
+
+    /// The supervisor of this actor, `None` for top level actors.
+    pub fn parent(&self) -> Option<&UntypedActorRef> {
+        self.parent.as_ref()
+    }
+
+    /// Registers this actor as a provider of `key`, usually called as
+    /// `ctx.register::<Self, _>(&key)`. It's deregistered when it stops.
+    /// Fails if `A` is not the type of this actor.
+    pub fn register<A: Handler<M>, M: Message>(&self, key: &ServiceKey<M>) -> Result<()> {
+        let myself = self
+            .myself
+            .downcast::<A>()
+            .ok_or_else(|| ActorError::WrongActorType(self.path.clone()))?;
+        self.system.receptionist().register(key, myself.recipient());
+        Ok(())
+    }
+
+    /// Selects actors relative to this one, `..` being the parent. Patterns
+    /// starting with `/` are absolute.
+    pub fn select(&self, pattern: &str) -> ActorSelection {
+        ActorSelection::new(self.system.clone(), Some(&self.path), pattern)
+    }
+
+    pub async fn children(&self) -> Vec<UntypedActorRef> {
+        self.system.children(&self.path).await
+    }
+
     pub async fn get<A: Actor>(&self, name: &str) -> Option<ActorRef<A>> {
-        let child = self.path.join(name);
+        let child = self.path.child(name).ok()?;
         self.system.get(&child).await
     }
 
-    pub async fn stop(&self, name: &str) {
-        let child = self.path.join(name);
-        self.system.stop_actor(&child).await;
+    /// Stops the child `name` and its descendants like `ActorSystem::stop_actor`,
+    /// but without waiting for them: the child is watched and
+    /// `Actor::terminated` is called once it's gone. A child that cancels the
+    /// stop keeps running and no `Terminated` is delivered for it.
+    pub async fn stop(&mut self, name: &str) -> Result<()> {
+        let path = self.path.child(name)?;
+        let children = self.children().await;
+        let Some(child) = children.iter().find(|child| *child.path() == path) else {
+            return Ok(());
+        };
+
+        self.watch_untyped(child).await;
+        // the child may need us to supervise it while it's stopping
+        let system = self.system.clone();
+        tokio::spawn(async move {
+            let _ = system.stop_actor(&path).await;
+        });
+        Ok(())
+    }
+
+    /// Stops this actor and its descendants once the current message is
+    /// handled. Unlike `ActorSystem::stop_actor` this doesn't wait, so it can
+    /// be used from a handler. `Actor::stopping` may still cancel the stop.
+    pub async fn stop_self(&self) -> Result<()> {
+        let (cancelled, _) = oneshot::channel();
+        let msg = SystemMessage::Stop {
+            cancelled: Some(cancelled),
+        };
+        self.myself.send_system(msg).await
+    }
+
+    /// Tells everyone waiting in `ActorSystem::stop_actor` that the actor
+    /// cancelled the stop.
+    pub(crate) fn stop_cancelled(&mut self) {
+        for cancelled in self.stop_requests.drain(..) {
+            let _ = cancelled.send(());
+        }
+    }
+
+    /// Calls `Actor::idle` whenever no message has been received for `timeout`.
+    /// Replaces any previously set receive timeout.
+    pub fn set_receive_timeout(&mut self, timeout: Duration) {
+        self.receive_timeout = Some(timeout);
+    }
+
+    pub fn cancel_receive_timeout(&mut self) {
+        self.receive_timeout = None;
+    }
+
+    pub fn receive_timeout(&self) -> Option<Duration> {
+        self.receive_timeout
+    }
+
+    /// Sends `msg` to this actor once after `delay`, replacing any timer with
+    /// the same key. `A` has to be the type of this actor.
+    pub fn start_single_timer<A, M>(&mut self, key: impl Into<String>, msg: M, delay: Duration)
+    where
+        A: Handler<M>,
+        M: Message,
+    {
+        let actor_ref = self.myself::<A>();
+        self.timers.start_single(key.into(), actor_ref, msg, delay);
+    }
+
+    /// Sends `msg` to this actor every `interval`, replacing any timer with
+    /// the same key. `A` has to be the type of this actor.
+    pub fn start_periodic_timer<A, M>(
+        &mut self,
+        key: impl Into<String>,
+        msg: M,
+        interval: Duration,
+        mode: TimerMode,
+    ) where
+        A: Handler<M>,
+        M: Message + Clone,
+    {
+        let actor_ref = self.myself::<A>();
+        self.timers
+            .start_periodic(key.into(), actor_ref, msg, interval, mode);
+    }
+
+    /// Cancels the timer, its message won't be delivered even if it is
+    /// already in the mailbox.
+    pub fn cancel_timer(&mut self, key: &str) {
+        self.timers.cancel(key);
+    }
+
+    pub fn is_timer_active(&self, key: &str) -> bool {
+        self.timers.is_active(key)
+    }
+
+    /// Handles the messages covered by `behavior` with it instead of the
+    /// actor's `Handler` impls until `unbecome` is called. Named this way as
+    /// `become` is a reserved word.
+    ///
+    /// Panics if `A` is not the type of this actor.
+    pub fn become_behavior<A: Actor>(&mut self, behavior: Behavior<A>) {
+        self.myself::<A>();
+        self.behaviors.push(behavior);
+    }
+
+    /// Returns to the behaviour that was active before the last
+    /// `become_behavior`, the actor's `Handler` impls at the bottom.
+    pub fn unbecome(&mut self) {
+        self.behaviors.pop();
+    }
+
+    /// Sets `msg`, the message being handled, aside until it is unstashed.
+    /// Whatever the handler returns is discarded, an asker gets the response
+    /// of the handler that processes the message once it's unstashed.
+    /// Messages of another type can't be stashed.
+    pub fn stash<M: Message>(&mut self, msg: M) -> Result<()> {
+        if self.handling != Some(TypeId::of::<M>()) {
+            return Err(ActorError::NotStashable(std::any::type_name::<M>()));
+        }
+        if self.stash_size >= self.stash_capacity {
+            return Err(ActorError::StashOverflow(self.path.clone()));
+        }
+
+        self.stashing = Some(Box::new(msg));
+        Ok(())
+    }
+
+    /// Processes the oldest stashed message before any new ones.
+    pub fn unstash_one(&mut self) {
+        self.unstash_requested = self.unstash_requested.saturating_add(1);
+    }
+
+    /// Processes all stashed messages, in the order they were stashed, before
+    /// any new ones.
+    pub fn unstash_all(&mut self) {
+        self.unstash_requested = usize::MAX;
+    }
+
+    pub fn stash_size(&self) -> usize {
+        self.stash_size
+    }
+
+    /// Watches `target` for termination. `Actor::terminated` is called once it
+    /// stops, or right away if it is not running anymore.
+    pub async fn watch<B: Actor>(&mut self, target: &ActorRef<B>) {
+        self.watch_untyped(&target.untyped()).await;
+    }
+
+    pub(crate) async fn watch_untyped(&mut self, target: &UntypedActorRef) {
+        if !self.watching.insert((target.path().clone(), target.uid())) {
+            return;
+        }
+
+        let watch = SystemMessage::Watch {
+            watcher: self.myself.clone(),
+        };
+        if target.send_system(watch).await.is_err() {
+            let terminated = Terminated {
+                path: target.path().clone(),
+                uid: target.uid(),
+                reason: "not running".into(),
+            };
+            // our own mailbox may be full, so don't block the handler on it
+            let myself = self.myself.clone();
+            tokio::spawn(async move {
+                let _ = myself
+                    .send_system(SystemMessage::Terminated(terminated))
+                    .await;
+            });
+        }
+    }
+
+    pub async fn unwatch<B: Actor>(&mut self, target: &ActorRef<B>) {
+        if self.watching.remove(&(target.path().clone(), target.uid())) {
+            let unwatch = SystemMessage::Unwatch {
+                watcher: self.myself.clone(),
+            };
+            let _ = target.sys_tell(unwatch).await;
+        }
+    }
+
+    pub(crate) async fn notify_watchers(&mut self, reason: &str) {
+        let terminated = Terminated {
+            path: self.path.clone(),
+            uid: self.myself.uid(),
+            reason: reason.to_string(),
+        };
+        self.watchers.notify(terminated).await;
+    }
+
+    /// Decides what happens to a failed child, applying the decision to its
+    /// siblings as well for all-for-one strategies.
+    pub(crate) async fn supervise(
+        &mut self,
+        child: &UntypedActorRef,
+        error: &ActorError,
+    ) -> Directive {
+        // removed again once the child is gone, see `SystemMessage::ChildTerminated`
+        let stats = self.restart_stats.entry(child.clone()).or_default();
+        let directive = self.supervisor_strategy.decide(error, stats);
+
+        if self.supervisor_strategy.is_all_for_one() {
+            for sibling in self.system.children(&self.path).await {
+                if sibling == *child {
+                    continue;
+                }
+
+                let _ = match directive {
+                    Directive::Restart => sibling.send_system(SystemMessage::Restart).await,
+                    Directive::Stop => sibling.poison().await,
+                    Directive::Resume | Directive::Escalate => Ok(()),
+                };
+            }
+        }
+
+        directive
     }
 }
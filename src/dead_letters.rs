@@ -0,0 +1,189 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use tokio::sync::broadcast;
+
+use crate::ActorPath;
+
+/// Subscribers lagging further behind miss dead letters, the counters don't.
+const DEAD_LETTERS_CAPACITY: usize = 1024;
+
+/// A message that could not be delivered, see `ActorSystem::dead_letters`.
+#[derive(Clone, Debug)]
+pub struct DeadLetter {
+    /// The actor the message was sent to.
+    pub path: ActorPath,
+    /// Incarnation of the actor, see `ActorRef::uid`.
+    pub uid: u64,
+    pub message_type: &'static str,
+    pub reason: DeadLetterReason,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DeadLetterReason {
+    /// The actor had already stopped.
+    MailboxClosed,
+    /// The mailbox was full and dropped the message.
+    Dropped,
+    /// The message was still queued or stashed when the actor stopped.
+    Stopped,
+}
+
+/// Number of dead letters of a system by reason.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct DeadLetterCounts {
+    pub mailbox_closed: u64,
+    pub dropped: u64,
+    pub stopped: u64,
+}
+
+impl DeadLetterCounts {
+    pub fn total(&self) -> u64 {
+        self.mailbox_closed + self.dropped + self.stopped
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DeadLetters {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    sender: broadcast::Sender<DeadLetter>,
+    mailbox_closed: AtomicU64,
+    dropped: AtomicU64,
+    stopped: AtomicU64,
+}
+
+impl DeadLetters {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                sender: broadcast::channel(DEAD_LETTERS_CAPACITY).0,
+                mailbox_closed: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+                stopped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub(crate) fn publish(
+        &self,
+        path: ActorPath,
+        uid: u64,
+        message_type: &'static str,
+        reason: DeadLetterReason,
+    ) {
+        let counter = match reason {
+            DeadLetterReason::MailboxClosed => &self.inner.mailbox_closed,
+            DeadLetterReason::Dropped => &self.inner.dropped,
+            DeadLetterReason::Stopped => &self.inner.stopped,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        // nobody may be listening
+        let _ = self.inner.sender.send(DeadLetter {
+            path,
+            uid,
+            message_type,
+            reason,
+        });
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<DeadLetter> {
+        self.inner.sender.subscribe()
+    }
+
+    pub(crate) fn counts(&self) -> DeadLetterCounts {
+        DeadLetterCounts {
+            mailbox_closed: self.inner.mailbox_closed.load(Ordering::Relaxed),
+            dropped: self.inner.dropped.load(Ordering::Relaxed),
+            stopped: self.inner.stopped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorError, OverflowStrategy, prelude::*};
+    use async_trait::async_trait;
+    use std::time::Duration;
+
+    struct Sleeper;
+    #[async_trait]
+    impl Actor for Sleeper {}
+
+    struct Sleep(Duration);
+    impl Message for Sleep {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Sleep> for Sleeper {
+        async fn handle(&mut self, msg: Sleep, _ctx: &mut ActorContext) {
+            tokio::time::sleep(msg.0).await;
+        }
+    }
+
+    // the short sleeps only end once the actor is stuck in its long one
+    #[tokio::test(start_paused = true)]
+    async fn undeliverable_messages_are_published() {
+        let system = ActorSystem::new();
+        let mut dead_letters = system.dead_letters();
+
+        let dropping = ActorProps::new(
+            || Sleeper,
+            || Box::new(DefaultActorSpawner::new()),
+            || {
+                Box::new(DefaultMailbox::with_overflow(
+                    1,
+                    OverflowStrategy::DropNewest,
+                ))
+            },
+        );
+        let dropping = system.spawn_props("dropping", dropping).await.unwrap();
+        dropping
+            .tell(Sleep(Duration::from_millis(50)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        dropping.tell(Sleep(Duration::ZERO)).await.unwrap();
+        dropping.tell(Sleep(Duration::ZERO)).await.unwrap();
+
+        let letter = dead_letters.recv().await.unwrap();
+        assert_eq!(letter.path, *dropping.path());
+        assert_eq!(letter.message_type, std::any::type_name::<Sleep>());
+        assert_eq!(letter.reason, DeadLetterReason::Dropped);
+
+        let poisoned = ActorProps::new(
+            || Sleeper,
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(PriorityMailbox::new(10)),
+        );
+        let poisoned = system.spawn_props("poisoned", poisoned).await.unwrap();
+        poisoned
+            .tell(Sleep(Duration::from_millis(50)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        poisoned.tell(Sleep(Duration::ZERO)).await.unwrap();
+        poisoned.poison().await.unwrap();
+
+        let letter = dead_letters.recv().await.unwrap();
+        assert_eq!(letter.path, *poisoned.path());
+        assert_eq!(letter.reason, DeadLetterReason::Stopped);
+
+        let result = poisoned.tell(Sleep(Duration::ZERO)).await;
+        assert!(matches!(result, Err(ActorError::SendError(_))));
+        let letter = dead_letters.recv().await.unwrap();
+        assert_eq!(letter.reason, DeadLetterReason::MailboxClosed);
+
+        let counts = system.dead_letter_counts();
+        assert_eq!(counts.total(), 3);
+        assert_eq!(counts.dropped, 1);
+    }
+}
@@ -1,6 +1,8 @@
+use std::{any::Any, sync::Arc};
+
 use thiserror::Error;
 
-use crate::ActorPath;
+use crate::{ActorPath, PathError};
 
 pub type Result<T> = std::result::Result<T, ActorError>;
 
@@ -12,9 +14,75 @@ pub enum ActorError {
     #[error("Actor creation failed")]
     CreateError(String),
 
+    #[error("Parent actor not found")]
+    ParentNotFound(ActorPath),
+
+    #[error("Parent actor has too many children")]
+    TooManyChildren(ActorPath),
+
     #[error("Sending message failed")]
     SendError(String),
 
+    #[error("Ask timed out")]
+    Timeout {
+        path: ActorPath,
+        message_type: &'static str,
+    },
+
     #[error("Actor runtime error")]
     RuntimeError(anyhow::Error),
+
+    #[error("No actor matches the selection")]
+    NotFound(String),
+
+    #[error("Mailbox is full")]
+    MailboxFull(ActorPath),
+
+    #[error("Stash is full")]
+    StashOverflow(ActorPath),
+
+    #[error("Only the message being handled can be stashed")]
+    NotStashable(&'static str),
+
+    #[error("Actor is of a different type")]
+    WrongActorType(ActorPath),
+
+    #[error("Actor cancelled stopping")]
+    StopCancelled(ActorPath),
+
+    #[error("Child actor failed")]
+    ChildFailed(ActorPath, Arc<ActorError>),
+
+    #[error("Invalid actor path")]
+    InvalidPath(#[from] PathError),
+
+    #[error("Actor panicked")]
+    Panicked {
+        /// Type of the message being handled, `None` if a lifecycle hook panicked.
+        message_type: Option<&'static str>,
+        payload: String,
+    },
+}
+
+impl ActorError {
+    pub(crate) fn panicked(
+        message_type: Option<&'static str>,
+        payload: Box<dyn Any + Send>,
+    ) -> Self {
+        ActorError::Panicked {
+            message_type,
+            payload: panic_message(&*payload),
+        }
+    }
+}
+
+/// Message a panic was raised with.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "Box<dyn Any>".into()
+    }
 }
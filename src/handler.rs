@@ -1,18 +1,56 @@
 use async_trait::async_trait;
-use std::marker::PhantomData;
+use std::{
+    any::TypeId,
+    future::Future,
+    marker::PhantomData,
+    panic::{self, AssertUnwindSafe},
+    task::Poll,
+};
 use tokio::sync::oneshot;
 
-use crate::{Actor, ActorContext, Handler, Message};
+use crate::{
+    Actor, ActorContext, ActorError, DeadLetterReason, Handler, Message, Priority, Result, behavior,
+};
 
 pub enum MessageHandlerResult {
     None,
-    Stop { reason: String },
+    Stop {
+        reason: String,
+    },
     Timeout,
+    /// The actor failed and has to be handled by its supervisor.
+    Failed(ActorError),
+    /// The supervisor restarts the actor.
+    Restart,
+    /// The message was stashed with `ActorContext::stash` and has to be kept
+    /// by the mailbox.
+    Stash,
 }
 
 #[async_trait]
 pub trait MessageHandler<A: Actor>: Send + Sync {
     async fn handle(&mut self, actor: &mut A, ctx: &mut ActorContext) -> MessageHandlerResult;
+
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn priority(&self) -> Priority {
+        Priority::Normal
+    }
+
+    /// Called instead of `handle` for messages left in the mailbox when the actor stops.
+    async fn reject(&mut self, _ctx: &mut ActorContext) {}
+
+    /// Called instead of `handle` when an interceptor refused the message,
+    /// `error` is the reply to an asker.
+    fn fail(&mut self, _error: ActorError) {}
+
+    /// System messages are handled by the actor system itself and are not
+    /// intercepted.
+    fn is_system(&self) -> bool {
+        false
+    }
 }
 
 pub type BoxedMessageHandler<A> = Box<dyn MessageHandler<A>>;
@@ -24,7 +62,7 @@ where
     A: Handler<M>,
 {
     payload: Option<M>,
-    reply_to: Option<oneshot::Sender<M::Response>>,
+    reply_to: Option<oneshot::Sender<Result<M::Response>>>,
     _actor: PhantomData<A>,
 }
 
@@ -33,7 +71,7 @@ where
     M: Message,
     A: Handler<M>,
 {
-    pub fn new(msg: M, reply_to: Option<oneshot::Sender<M::Response>>) -> Self {
+    pub fn new(msg: M, reply_to: Option<oneshot::Sender<Result<M::Response>>>) -> Self {
         Envelope {
             payload: Some(msg),
             reply_to,
@@ -49,16 +87,55 @@ where
     A: Handler<M>,
 {
     async fn handle(&mut self, actor: &mut A, ctx: &mut ActorContext) -> MessageHandlerResult {
-        let result = actor.handle(self.payload.take().unwrap(), ctx).await;
+        let msg = self.payload.take().unwrap();
+        ctx.handling = Some(TypeId::of::<M>());
+        let result = match behavior::handle(actor, msg, ctx).await {
+            Ok(result) => result,
+            Err(msg) => actor.handle(msg, ctx).await,
+        };
+
+        if let Some(stashed) = ctx.stashing.take()
+            && let Ok(msg) = stashed.downcast::<M>()
+        {
+            // keep `reply_to`, the asker gets the response once it's unstashed
+            self.payload = Some(*msg);
+            return MessageHandlerResult::Stash;
+        }
 
+        // the asker may have given up waiting already
         if let Some(reply_to) = self.reply_to.take() {
-            reply_to.send(result).unwrap_or_else(|_failed| {
-                eprintln!("Failed to send back response!");
-            })
+            let _ = reply_to.send(Ok(result));
         }
 
         MessageHandlerResult::None
     }
+
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    fn fail(&mut self, error: ActorError) {
+        self.payload = None;
+        if let Some(reply_to) = self.reply_to.take() {
+            let _ = reply_to.send(Err(error));
+        }
+    }
+
+    fn priority(&self) -> Priority {
+        self.payload
+            .as_ref()
+            .map_or(Priority::Normal, Message::priority)
+    }
+
+    async fn reject(&mut self, ctx: &mut ActorContext) {
+        if self.payload.take().is_some() {
+            let reason = DeadLetterReason::Stopped;
+            let uid = ctx.myself.uid();
+            ctx.system
+                .dead_letters
+                .publish(ctx.path.clone(), uid, self.message_type(), reason);
+        }
+    }
 }
 
 #[async_trait]
@@ -68,6 +145,8 @@ pub trait SystemHandler<M: Message>: Actor {
         msg: M,
         ctx: &mut ActorContext,
     ) -> (M::Response, MessageHandlerResult);
+
+    async fn reject(_msg: M, _ctx: &mut ActorContext) {}
 }
 
 #[derive(Debug)]
@@ -104,12 +183,38 @@ where
     async fn handle(&mut self, actor: &mut A, ctx: &mut ActorContext) -> MessageHandlerResult {
         let (result, handler_result) = actor.handle(self.payload.take().unwrap(), ctx).await;
 
+        // the asker may have given up waiting already
         if let Some(reply_to) = self.reply_to.take() {
-            reply_to.send(result).unwrap_or_else(|_failed| {
-                eprintln!("Failed to send back response!");
-            })
+            let _ = reply_to.send(result);
         }
 
         handler_result
     }
+
+    fn message_type(&self) -> &'static str {
+        std::any::type_name::<M>()
+    }
+
+    async fn reject(&mut self, ctx: &mut ActorContext) {
+        if let Some(msg) = self.payload.take() {
+            A::reject(msg, ctx).await;
+        }
+    }
+
+    fn is_system(&self) -> bool {
+        true
+    }
+}
+
+/// Runs `fut` to completion, catching any panic raised while polling it.
+pub(crate) async fn catch_unwind<F: Future>(fut: F) -> std::thread::Result<F::Output> {
+    let mut fut = std::pin::pin!(fut);
+    std::future::poll_fn(move |cx| {
+        match panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    })
+    .await
 }
@@ -0,0 +1,238 @@
+use async_trait::async_trait;
+use std::{sync::Arc, time::Duration};
+
+use crate::{ActorContext, MessageHandlerResult, Result};
+
+/// Wraps the handling of every message sent to an actor, see
+/// `ActorProps::with_interceptor` and `ActorSystemBuilder::with_interceptor`.
+/// System messages are not intercepted.
+#[async_trait]
+pub trait Interceptor: Send + Sync + 'static {
+    /// Called before a message is handled. An error skips the handler and
+    /// becomes the reply of an `ask`.
+    async fn before(&self, _ctx: &ActorContext, _message_type: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after a message was handled with the result and how long it took.
+    async fn after(
+        &self,
+        _ctx: &ActorContext,
+        _message_type: &'static str,
+        _result: &MessageHandlerResult,
+        _elapsed: Duration,
+    ) {
+    }
+}
+
+/// Interceptor chain of an actor. `before` runs in the order the interceptors
+/// were added, system defaults first, `after` in reverse.
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors {
+    chain: Arc<[Arc<dyn Interceptor>]>,
+}
+
+impl Interceptors {
+    pub(crate) fn with(&self, interceptor: impl Interceptor) -> Self {
+        let interceptor: Arc<dyn Interceptor> = Arc::new(interceptor);
+        self.then(&Self {
+            chain: Arc::new([interceptor]),
+        })
+    }
+
+    /// Chain running `self` before `next`.
+    pub(crate) fn then(&self, next: &Self) -> Self {
+        Self {
+            chain: self
+                .chain
+                .iter()
+                .chain(next.chain.iter())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    pub(crate) async fn before(
+        &self,
+        ctx: &ActorContext,
+        message_type: &'static str,
+    ) -> Result<()> {
+        for interceptor in self.chain.iter() {
+            interceptor.before(ctx, message_type).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn after(
+        &self,
+        ctx: &ActorContext,
+        message_type: &'static str,
+        result: &MessageHandlerResult,
+        elapsed: Duration,
+    ) {
+        for interceptor in self.chain.iter().rev() {
+            interceptor.after(ctx, message_type, result, elapsed).await;
+        }
+    }
+}
+
+impl std::fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interceptors")
+            .field("len", &self.chain.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorError, LifecycleEventKind, prelude::*};
+    use std::sync::Mutex;
+
+    struct Echo;
+    #[async_trait]
+    impl Actor for Echo {}
+
+    struct Say(&'static str);
+    impl Message for Say {
+        type Response = &'static str;
+    }
+
+    #[async_trait]
+    impl Handler<Say> for Echo {
+        async fn handle(&mut self, msg: Say, _ctx: &mut ActorContext) -> &'static str {
+            msg.0
+        }
+    }
+
+    struct Forbidden;
+    impl Message for Forbidden {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Forbidden> for Echo {
+        async fn handle(&mut self, _msg: Forbidden, _ctx: &mut ActorContext) {
+            panic!("must not be handled");
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Interceptor for Recorder {
+        async fn before(&self, ctx: &ActorContext, message_type: &'static str) -> Result<()> {
+            let event = format!("before {} {}", ctx.path.name(), short(message_type));
+            self.0.lock().unwrap().push(event);
+            Ok(())
+        }
+
+        async fn after(
+            &self,
+            _ctx: &ActorContext,
+            message_type: &'static str,
+            result: &MessageHandlerResult,
+            _elapsed: Duration,
+        ) {
+            let handled = matches!(result, MessageHandlerResult::None);
+            let event = format!("after {} {handled}", short(message_type));
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    struct Guard;
+
+    #[async_trait]
+    impl Interceptor for Guard {
+        async fn before(&self, _ctx: &ActorContext, message_type: &'static str) -> Result<()> {
+            if message_type == std::any::type_name::<Forbidden>() {
+                return Err(ActorError::RuntimeError(anyhow::anyhow!("forbidden")));
+            }
+            Ok(())
+        }
+    }
+
+    struct Panicky;
+
+    #[async_trait]
+    impl Interceptor for Panicky {
+        async fn before(&self, _ctx: &ActorContext, message_type: &'static str) -> Result<()> {
+            if message_type == std::any::type_name::<Forbidden>() {
+                panic!("before");
+            }
+            Ok(())
+        }
+
+        async fn after(
+            &self,
+            _ctx: &ActorContext,
+            _message_type: &'static str,
+            _result: &MessageHandlerResult,
+            _elapsed: Duration,
+        ) {
+            panic!("after");
+        }
+    }
+
+    fn short(message_type: &str) -> &str {
+        message_type.rsplit("::").next().unwrap()
+    }
+
+    #[tokio::test]
+    async fn interceptors_wrap_handlers() {
+        let recorder = Recorder::default();
+        let system = ActorSystem::builder("default")
+            .with_interceptor(recorder.clone())
+            .build()
+            .unwrap();
+        let props = ActorProps::new(
+            || Echo,
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(DefaultMailbox::new(10)),
+        )
+        .with_interceptor(Guard);
+        let echo = system.spawn_props("echo", props).await.unwrap();
+
+        assert_eq!(echo.ask(Say("hi")).await.unwrap(), "hi");
+        let result = echo.ask(Forbidden).await;
+        assert!(matches!(result, Err(ActorError::RuntimeError(e)) if e.to_string() == "forbidden"));
+        echo.poison().await.unwrap();
+
+        let events = recorder.0.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            vec!["before echo Say", "after Say true", "before echo Forbidden"]
+        );
+    }
+
+    #[tokio::test]
+    async fn panicking_interceptors_fail_the_actor() {
+        let system = ActorSystem::new();
+        let mut events = system.subscribe_lifecycle();
+        let props = ActorProps::new(
+            || Echo,
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(DefaultMailbox::new(10)),
+        )
+        .with_interceptor(Panicky);
+        let echo = system.spawn_props("echo", props).await.unwrap();
+
+        assert!(echo.ask(Forbidden).await.is_err());
+        assert_eq!(echo.ask(Say("hi")).await.unwrap(), "hi");
+
+        let mut restarts = 0;
+        while restarts < 2 {
+            let event = events.recv().await.unwrap();
+            if event.kind == LifecycleEventKind::Restarted {
+                restarts += 1;
+            }
+        }
+        assert!(!echo.is_closed());
+    }
+}
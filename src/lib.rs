@@ -1,27 +1,58 @@
+mod behavior;
+mod channel;
 mod context;
+mod dead_letters;
 mod error;
 mod handler;
+mod interceptor;
+mod lifecycle;
 mod mailbox;
+mod path;
 pub mod prelude;
 mod props;
+mod receptionist;
 mod reference;
+mod registry;
+mod runner;
+mod selection;
 mod spawner;
+mod stash;
+mod supervisor;
 mod system;
+mod timer;
 
 use async_trait::async_trait;
 
+pub use behavior::{Behavior, BehaviorHandler};
 pub use context::ActorContext;
+pub use dead_letters::{DeadLetter, DeadLetterCounts, DeadLetterReason};
 pub use error::{ActorError, Result};
+pub use interceptor::Interceptor;
+pub use lifecycle::{LifecycleEvent, LifecycleEventKind};
 pub use mailbox::{
-    ActorPath, ActorRef, BoxedMessageHandler, DefaultMailbox, Mailbox, MessageHandler,
-    MessageHandlerResult, MessageProcessor, Receiver, Sender, WeakSender,
+    ActorPath, ActorRef, BoxedMessageHandler, ControlSender, DefaultMailbox, Mailbox,
+    MessageHandler, MessageHandlerResult, MessageProcessor, OverflowStrategy, Priority,
+    PriorityMailbox, Receiver, Sender, Throughput, WeakSender, channel,
 };
+pub use path::{Address, PathError};
 pub use props::ActorProps;
-pub use spawner::{ActorSpawner, DefaultActorSpawner};
-pub use system::ActorSystem;
+pub use receptionist::{Receptionist, ServiceKey};
+pub use reference::{Recipient, UntypedActorRef};
+pub use runner::ActorRunner;
+pub use selection::{ActorSelection, TellReport};
+pub use spawner::{ActorSpawner, ActorTask, DefaultActorSpawner, TaskSpawner};
+pub use supervisor::{Decider, Directive, SupervisorStrategy};
+pub use system::{ActorSystem, ActorSystemBuilder, ShutdownReport, Terminated};
+pub use timer::TimerMode;
 
 pub trait Message: Send + Sync + 'static {
     type Response: Send + Sync + 'static;
+
+    /// Messages of higher priority are handled first by a `PriorityMailbox`,
+    /// other mailboxes ignore it.
+    fn priority(&self) -> Priority {
+        Priority::Normal
+    }
 }
 
 pub enum StoppingResult {
@@ -48,6 +79,16 @@ pub trait Actor: Send + Sync + 'static {
     }
 
     async fn stopped(&mut self, _ctx: &mut ActorContext) {}
+
+    /// Called when no message arrived within the receive timeout set with
+    /// `ActorContext::set_receive_timeout`, and again every timeout while the
+    /// actor stays idle. Return `MessageHandlerResult::Stop` to passivate it.
+    async fn idle(&mut self, _ctx: &mut ActorContext) -> MessageHandlerResult {
+        MessageHandlerResult::None
+    }
+
+    /// Called when an actor watched with `ActorContext::watch` has stopped.
+    async fn terminated(&mut self, _ctx: &mut ActorContext, _msg: Terminated) {}
 }
 
 #[async_trait]
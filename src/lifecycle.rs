@@ -0,0 +1,150 @@
+use tokio::sync::broadcast;
+
+use crate::ActorPath;
+
+/// Subscribers lagging further behind miss events.
+const LIFECYCLE_CAPACITY: usize = 1024;
+
+/// Something that happened to an actor, see `ActorSystem::subscribe_lifecycle`.
+#[derive(Clone, Debug)]
+pub struct LifecycleEvent {
+    pub path: ActorPath,
+    /// Incarnation of the actor, see `ActorRef::uid`.
+    pub uid: u64,
+    pub actor_type: &'static str,
+    pub kind: LifecycleEventKind,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LifecycleEventKind {
+    /// The actor was added to the registry.
+    Spawned,
+    /// `Actor::started` succeeded.
+    Started,
+    /// `Actor::restarting` succeeded.
+    Restarted,
+    /// The actor stopped handling messages and is shutting down its children.
+    Stopping,
+    /// The actor is gone, `reason` is what watchers get in `Terminated`.
+    Stopped { reason: String },
+    /// `Actor::stopping` or `Actor::stopped` panicked, which no supervisor
+    /// gets to handle as the actor is stopping anyway.
+    HookPanicked { hook: &'static str, payload: String },
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Lifecycle {
+    sender: broadcast::Sender<LifecycleEvent>,
+}
+
+impl Lifecycle {
+    pub(crate) fn new() -> Self {
+        Self {
+            sender: broadcast::channel(LIFECYCLE_CAPACITY).0,
+        }
+    }
+
+    pub(crate) fn publish<A>(&self, path: &ActorPath, uid: u64, kind: LifecycleEventKind) {
+        self.publish_as(path, uid, std::any::type_name::<A>(), kind);
+    }
+
+    /// Like `publish`, for callers that only know the name of the actor type.
+    pub(crate) fn publish_as(
+        &self,
+        path: &ActorPath,
+        uid: u64,
+        actor_type: &'static str,
+        kind: LifecycleEventKind,
+    ) {
+        // nobody may be listening
+        let _ = self.sender.send(LifecycleEvent {
+            path: path.clone(),
+            uid,
+            actor_type,
+            kind,
+        });
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.sender.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use async_trait::async_trait;
+
+    struct Flaky;
+    #[async_trait]
+    impl Actor for Flaky {}
+
+    struct Fail;
+    impl Message for Fail {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Fail> for Flaky {
+        async fn handle(&mut self, _msg: Fail, _ctx: &mut ActorContext) {
+            panic!("flaky");
+        }
+    }
+
+    #[tokio::test]
+    async fn lifecycle_events_are_published() {
+        let system = ActorSystem::new();
+        let mut events = system.subscribe_lifecycle();
+
+        let flaky = system.spawn("flaky", || Flaky, 10).await.unwrap();
+        let _ = flaky.ask(Fail).await;
+        flaky.poison().await.unwrap();
+
+        let mut kinds = Vec::new();
+        for _ in 0..5 {
+            let event = events.recv().await.unwrap();
+            assert_eq!(event.path, *flaky.path());
+            assert_eq!(event.uid, flaky.uid());
+            assert_eq!(event.actor_type, std::any::type_name::<Flaky>());
+            kinds.push(event.kind);
+        }
+        let stopped = LifecycleEventKind::Stopped {
+            reason: "poisoned".into(),
+        };
+        assert_eq!(
+            kinds,
+            [
+                LifecycleEventKind::Spawned,
+                LifecycleEventKind::Started,
+                LifecycleEventKind::Restarted,
+                LifecycleEventKind::Stopping,
+                stopped,
+            ]
+        );
+    }
+
+    struct Clumsy;
+
+    #[async_trait]
+    impl Actor for Clumsy {
+        async fn stopped(&mut self, _ctx: &mut ActorContext) {
+            panic!("clumsy");
+        }
+    }
+
+    #[tokio::test]
+    async fn panicking_stopped_hook_is_published() {
+        let system = ActorSystem::new();
+        let mut events = system.subscribe_lifecycle();
+
+        let clumsy = system.spawn("clumsy", || Clumsy, 10).await.unwrap();
+        clumsy.poison().await.unwrap();
+
+        let panicked = LifecycleEventKind::HookPanicked {
+            hook: "stopped",
+            payload: "clumsy".into(),
+        };
+        while events.recv().await.unwrap().kind != panicked {}
+    }
+}
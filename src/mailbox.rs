@@ -1,38 +1,296 @@
 use async_trait::async_trait;
-use std::marker::PhantomData;
-use tokio::sync::mpsc;
+use std::{cmp::Ordering, collections::BinaryHeap, marker::PhantomData, time::Duration};
+use tokio::{sync::mpsc, time::Instant};
 
 pub use crate::handler::{BoxedMessageHandler, MessageHandler, MessageHandlerResult};
-pub use crate::reference::{ActorPath, ActorRef};
+pub use crate::path::ActorPath;
+pub use crate::reference::ActorRef;
 
-use crate::{Actor, ActorContext};
+pub use crate::channel::{OverflowStrategy, channel};
 
-pub type Receiver<A> = mpsc::Receiver<BoxedMessageHandler<A>>;
-pub type Sender<A> = mpsc::Sender<BoxedMessageHandler<A>>;
-pub type WeakSender<A> = mpsc::WeakSender<BoxedMessageHandler<A>>;
+use crate::{
+    Actor, ActorContext, ActorError, LifecycleEventKind, error::panic_message,
+    handler::catch_unwind, stash::Stash,
+};
+
+pub type Receiver<A> = crate::channel::Receiver<BoxedMessageHandler<A>>;
+pub type Sender<A> = crate::channel::Sender<BoxedMessageHandler<A>>;
+pub type WeakSender<A> = crate::channel::WeakSender<BoxedMessageHandler<A>>;
+/// Sender of the control lane, system messages sent through it never wait
+/// behind user messages.
+pub type ControlSender<A> = mpsc::UnboundedSender<BoxedMessageHandler<A>>;
+
+/// Priority of a message in a `PriorityMailbox`, see `Message::priority`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// How long an actor keeps the worker thread before it yields to the
+/// scheduler, set with `ActorProps::with_throughput`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Throughput {
+    messages: usize,
+    time_limit: Option<Duration>,
+    fair: bool,
+}
+
+impl Throughput {
+    /// Yields after handling `messages` messages.
+    pub fn new(messages: usize) -> Self {
+        Self {
+            messages: messages.max(1),
+            time_limit: None,
+            fair: false,
+        }
+    }
+
+    /// Also yields once a batch took longer than `limit`.
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Handles pending system messages before every batch, even those queued
+    /// behind user messages in a mailbox without a control lane.
+    pub fn with_fairness(mut self) -> Self {
+        self.fair = true;
+        self
+    }
+}
+
+impl Default for Throughput {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
 
 #[async_trait]
 pub trait MessageProcessor<A: Actor> {
-    async fn process_messages(&mut self, ctx: &mut ActorContext, actor: &mut A);
+    /// Processes messages until the actor stops, fails or has to be restarted
+    /// and returns the result that interrupted processing.
+    async fn process_messages(
+        &mut self,
+        ctx: &mut ActorContext,
+        actor: &mut A,
+    ) -> MessageHandlerResult;
+
+    /// Closes the mailbox and rejects every message still queued in it.
+    async fn drain(&mut self, ctx: &mut ActorContext);
 }
 
 pub trait Mailbox<A: Actor>: MessageProcessor<A> + Send + 'static {
     fn take_sender(&mut self) -> Sender<A>;
+
+    /// Mailboxes with a control lane return its sender, otherwise system
+    /// messages are sent like any other message.
+    fn take_control_sender(&mut self) -> Option<ControlSender<A>> {
+        None
+    }
+}
+
+/// Where the shared processing loop gets its messages from.
+#[async_trait]
+trait Queue<A: Actor>: Send {
+    /// Waits for the next message, `None` once the queue is closed and empty.
+    async fn recv(&mut self) -> Option<BoxedMessageHandler<A>>;
+
+    /// Returns a pending control message, which goes before unstashed ones.
+    fn try_recv_control(&mut self) -> Option<BoxedMessageHandler<A>> {
+        None
+    }
+
+    /// Returns a pending system message, wherever it is queued.
+    fn try_recv_system(&mut self) -> Option<BoxedMessageHandler<A>> {
+        self.try_recv_control()
+    }
+
+    fn close(&mut self);
+}
+
+async fn process_messages<A: Actor>(
+    queue: &mut impl Queue<A>,
+    stash: &mut Stash<A>,
+    ctx: &mut ActorContext,
+    actor: &mut A,
+) -> MessageHandlerResult {
+    let mut batch_started = Instant::now();
+    let mut handled = 0;
+    let mut check_system = false;
+    loop {
+        let throughput = ctx.throughput;
+        if handled >= throughput.messages
+            || throughput
+                .time_limit
+                .is_some_and(|limit| batch_started.elapsed() >= limit)
+        {
+            tokio::task::yield_now().await;
+            batch_started = Instant::now();
+            handled = 0;
+            check_system = throughput.fair;
+        }
+
+        let system = if check_system {
+            queue.try_recv_system()
+        } else {
+            None
+        };
+        check_system = system.is_some();
+
+        let received = if let Some(msg) = system.or_else(|| queue.try_recv_control()) {
+            Some(Some(msg))
+        } else if let Some(msg) = stash.pop(ctx) {
+            Some(Some(msg))
+        } else if let Some(timeout) = ctx.receive_timeout {
+            tokio::time::timeout(timeout, queue.recv()).await.ok()
+        } else {
+            Some(queue.recv().await)
+        };
+
+        let mut result = match received {
+            Some(Some(mut msg)) => {
+                handled += 1;
+                let message_type = msg.message_type();
+                let interceptors = ctx.interceptors.clone();
+                // the asker gets the panic as well, instead of a closed channel
+                let panicked = |msg: &mut BoxedMessageHandler<A>, payload| {
+                    let error = ActorError::panicked(Some(message_type), payload);
+                    if let ActorError::Panicked { payload, .. } = &error {
+                        msg.fail(ActorError::Panicked {
+                            message_type: Some(message_type),
+                            payload: payload.clone(),
+                        });
+                    }
+                    MessageHandlerResult::Failed(error)
+                };
+                let intercepted = !interceptors.is_empty() && !msg.is_system();
+                if intercepted {
+                    // a panicking interceptor fails the actor like a panicking handler
+                    match catch_unwind(interceptors.before(ctx, message_type)).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(error)) => {
+                            msg.fail(error);
+                            continue;
+                        }
+                        Err(payload) => return panicked(&mut msg, payload),
+                    }
+                }
+
+                let started = Instant::now();
+                let mut result = match catch_unwind(msg.handle(actor, ctx)).await {
+                    Ok(result) => result,
+                    Err(payload) => panicked(&mut msg, payload),
+                };
+
+                ctx.stashing = None;
+                ctx.handling = None;
+                if intercepted {
+                    let elapsed = started.elapsed();
+                    let after = interceptors.after(ctx, message_type, &result, elapsed);
+                    if let Err(payload) = catch_unwind(after).await {
+                        result = panicked(&mut msg, payload);
+                    }
+                }
+                if let MessageHandlerResult::Stash = result {
+                    stash.push(msg, ctx);
+                }
+                result
+            }
+            Some(None) => break,
+            None => MessageHandlerResult::Timeout,
+        };
+
+        if let MessageHandlerResult::Timeout = result {
+            result = catch_unwind(actor.idle(ctx))
+                .await
+                .unwrap_or_else(|payload| {
+                    MessageHandlerResult::Failed(ActorError::panicked(None, payload))
+                });
+        }
+
+        match result {
+            MessageHandlerResult::Stop { reason } => {
+                // a panicking `stopping` hook can't cancel the stop, neither
+                // can an actor whose parent is stopping
+                let stopping = catch_unwind(actor.stopping(ctx, &reason))
+                    .await
+                    .unwrap_or_else(|payload| {
+                        let kind = LifecycleEventKind::HookPanicked {
+                            hook: "stopping",
+                            payload: panic_message(&*payload),
+                        };
+                        let uid = ctx.myself.uid();
+                        ctx.system.lifecycle.publish::<A>(&ctx.path, uid, kind);
+                        crate::StoppingResult::Stop
+                    });
+                match stopping {
+                    crate::StoppingResult::Cancel if !ctx.stop_forced => ctx.stop_cancelled(),
+                    _ => {
+                        println!("stop: reason={reason}");
+                        queue.close();
+                        return MessageHandlerResult::Stop { reason };
+                    }
+                }
+            }
+            MessageHandlerResult::Timeout => {}
+            MessageHandlerResult::None => {}
+            MessageHandlerResult::Stash => {}
+            result @ (MessageHandlerResult::Failed(_) | MessageHandlerResult::Restart) => {
+                return result;
+            }
+        }
+    }
+
+    MessageHandlerResult::Stop {
+        reason: "mailbox closed".into(),
+    }
+}
+
+async fn drain<A: Actor>(queue: &mut impl Queue<A>, stash: &mut Stash<A>, ctx: &mut ActorContext) {
+    for mut msg in stash.drain(ctx) {
+        msg.reject(ctx).await;
+    }
+
+    queue.close();
+    while let Some(mut msg) = queue.recv().await {
+        msg.reject(ctx).await;
+    }
 }
 
 #[derive(Debug)]
 pub struct DefaultMailbox<A: Actor> {
     sender: Option<Sender<A>>,
     receiver: Receiver<A>,
+    stash: Stash<A>,
     _actor: PhantomData<A>,
 }
 
 impl<A: Actor> DefaultMailbox<A> {
+    /// Mailbox of `buffer` messages, senders wait while it's full. Panics if
+    /// `buffer` is 0.
     pub fn new(buffer: usize) -> Self {
-        let (sender, receiver) = mpsc::channel(buffer);
+        Self::with_overflow(buffer, OverflowStrategy::Backpressure)
+    }
+
+    /// Mailbox of `buffer` messages, `overflow` decides what happens to
+    /// messages sent while it's full.
+    pub fn with_overflow(buffer: usize, overflow: OverflowStrategy) -> Self {
+        Self::from_channel(channel(Some(buffer), overflow))
+    }
+
+    /// Mailbox without a limit, for actors that are known to keep up.
+    pub fn unbounded() -> Self {
+        Self::from_channel(channel(None, OverflowStrategy::Backpressure))
+    }
+
+    fn from_channel((sender, receiver): (Sender<A>, Receiver<A>)) -> Self {
         Self {
             sender: Some(sender),
             receiver,
+            stash: Stash::default(),
             _actor: PhantomData,
         }
     }
@@ -44,22 +302,528 @@ impl<A: Actor> Mailbox<A> for DefaultMailbox<A> {
     }
 }
 
+#[async_trait]
+impl<A: Actor> Queue<A> for Receiver<A> {
+    async fn recv(&mut self) -> Option<BoxedMessageHandler<A>> {
+        crate::channel::Receiver::recv(self).await
+    }
+
+    fn try_recv_system(&mut self) -> Option<BoxedMessageHandler<A>> {
+        // system messages are the only forced ones
+        self.try_recv_forced()
+    }
+
+    fn close(&mut self) {
+        crate::channel::Receiver::close(self);
+    }
+}
+
 #[async_trait]
 impl<A: Actor> MessageProcessor<A> for DefaultMailbox<A> {
-    async fn process_messages(&mut self, ctx: &mut ActorContext, actor: &mut A) {
-        while let Some(mut msg) = self.receiver.recv().await {
-            match msg.handle(actor, ctx).await {
-                MessageHandlerResult::Stop { reason } => match actor.stopping(ctx, &reason).await {
-                    crate::StoppingResult::Stop => {
-                        println!("stop: reason={reason}");
-                        self.receiver.close();
-                        break;
-                    }
-                    crate::StoppingResult::Cancel => {}
+    async fn process_messages(
+        &mut self,
+        ctx: &mut ActorContext,
+        actor: &mut A,
+    ) -> MessageHandlerResult {
+        process_messages(&mut self.receiver, &mut self.stash, ctx, actor).await
+    }
+
+    async fn drain(&mut self, ctx: &mut ActorContext) {
+        drain(&mut self.receiver, &mut self.stash, ctx).await
+    }
+}
+
+/// Mailbox that handles messages by their `Message::priority`, messages of
+/// the same priority in the order they arrived. System messages go through a
+/// separate control lane which is always drained first, so they neither wait
+/// behind nor get blocked by a full mailbox.
+#[derive(Debug)]
+pub struct PriorityMailbox<A: Actor> {
+    sender: Option<Sender<A>>,
+    control_sender: Option<ControlSender<A>>,
+    lanes: PriorityLanes<A>,
+    stash: Stash<A>,
+}
+
+impl<A: Actor> PriorityMailbox<A> {
+    /// Up to `buffer` messages are queued by priority and up to another
+    /// `buffer` wait to be queued before senders are blocked. Panics if
+    /// `buffer` is 0.
+    pub fn new(buffer: usize) -> Self {
+        let (sender, receiver) = channel(Some(buffer), OverflowStrategy::Backpressure);
+        let (control_sender, control) = mpsc::unbounded_channel();
+        Self {
+            sender: Some(sender),
+            control_sender: Some(control_sender),
+            lanes: PriorityLanes {
+                control,
+                receiver,
+                queued: BinaryHeap::new(),
+                capacity: buffer,
+                next_seq: 0,
+            },
+            stash: Stash::default(),
+        }
+    }
+}
+
+impl<A: Actor> Mailbox<A> for PriorityMailbox<A> {
+    fn take_sender(&mut self) -> Sender<A> {
+        self.sender.take().unwrap()
+    }
+
+    fn take_control_sender(&mut self) -> Option<ControlSender<A>> {
+        self.control_sender.take()
+    }
+}
+
+#[async_trait]
+impl<A: Actor> MessageProcessor<A> for PriorityMailbox<A> {
+    async fn process_messages(
+        &mut self,
+        ctx: &mut ActorContext,
+        actor: &mut A,
+    ) -> MessageHandlerResult {
+        process_messages(&mut self.lanes, &mut self.stash, ctx, actor).await
+    }
+
+    async fn drain(&mut self, ctx: &mut ActorContext) {
+        drain(&mut self.lanes, &mut self.stash, ctx).await
+    }
+}
+
+#[derive(Debug)]
+struct PriorityLanes<A: Actor> {
+    control: mpsc::UnboundedReceiver<BoxedMessageHandler<A>>,
+    receiver: Receiver<A>,
+    queued: BinaryHeap<Queued<A>>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+impl<A: Actor> PriorityLanes<A> {
+    /// Moves waiting messages into the priority queue while it has room.
+    fn fill(&mut self) {
+        while self.queued.len() < self.capacity {
+            let Some(msg) = self.receiver.try_recv() else {
+                break;
+            };
+            self.next_seq += 1;
+            self.queued.push(Queued {
+                priority: msg.priority(),
+                seq: self.next_seq,
+                msg,
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl<A: Actor> Queue<A> for PriorityLanes<A> {
+    async fn recv(&mut self) -> Option<BoxedMessageHandler<A>> {
+        if let Some(msg) = self.try_recv_control() {
+            return Some(msg);
+        }
+
+        self.fill();
+        if let Some(queued) = self.queued.pop() {
+            return Some(queued.msg);
+        }
+
+        tokio::select! {
+            biased;
+            Some(msg) = self.control.recv() => Some(msg),
+            msg = self.receiver.recv() => msg,
+        }
+    }
+
+    fn try_recv_control(&mut self) -> Option<BoxedMessageHandler<A>> {
+        self.control.try_recv().ok()
+    }
+
+    fn close(&mut self) {
+        self.control.close();
+        self.receiver.close();
+    }
+}
+
+struct Queued<A: Actor> {
+    priority: Priority,
+    seq: u64,
+    msg: BoxedMessageHandler<A>,
+}
+
+impl<A: Actor> std::fmt::Debug for Queued<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Queued")
+            .field("priority", &self.priority)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
+
+impl<A: Actor> PartialEq for Queued<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<A: Actor> Eq for Queued<A> {}
+
+impl<A: Actor> PartialOrd for Queued<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Actor> Ord for Queued<A> {
+    /// Higher priority first, then the older message.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::{
+        ActorError, Directive, MessageHandlerResult, Priority, PriorityMailbox, SupervisorStrategy,
+        Throughput,
+    };
+    use async_trait::async_trait;
+    use tokio::sync::{mpsc, oneshot};
+
+    struct Parent {
+        stopped: mpsc::UnboundedSender<()>,
+    }
+    #[async_trait]
+    impl Actor for Parent {}
+
+    struct SpawnChild;
+    impl Message for SpawnChild {
+        type Response = ActorRef<Child>;
+    }
+
+    #[async_trait]
+    impl Handler<SpawnChild> for Parent {
+        async fn handle(&mut self, _msg: SpawnChild, ctx: &mut ActorContext) -> ActorRef<Child> {
+            let stopped = self.stopped.clone();
+            ctx.spawn(
+                "child",
+                move || Child {
+                    stopped: stopped.clone(),
                 },
-                MessageHandlerResult::Timeout => {}
-                MessageHandlerResult::None => {}
+                10,
+            )
+            .await
+            .unwrap()
+        }
+    }
+
+    struct Child {
+        stopped: mpsc::UnboundedSender<()>,
+    }
+
+    #[async_trait]
+    impl Actor for Child {
+        async fn stopped(&mut self, _ctx: &mut ActorContext) {
+            self.stopped.send(()).unwrap();
+        }
+    }
+
+    struct Ping;
+    impl Message for Ping {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Ping> for Child {
+        async fn handle(&mut self, _msg: Ping, _ctx: &mut ActorContext) {}
+    }
+
+    struct Panic;
+    impl Message for Panic {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Panic> for Child {
+        async fn handle(&mut self, _msg: Panic, _ctx: &mut ActorContext) {
+            panic!("boom");
+        }
+    }
+
+    #[tokio::test]
+    async fn panic_is_isolated_to_message() {
+        let system = ActorSystem::new();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let child = system
+            .spawn(
+                "child",
+                move || Child {
+                    stopped: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+
+        let error = child.ask(Panic).await.unwrap_err();
+        assert!(matches!(
+            error,
+            ActorError::Panicked { message_type: Some(message_type), payload }
+                if message_type == std::any::type_name::<Panic>() && payload == "boom"
+        ));
+        assert!(child.ask(Ping).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn panic_reports_failure_and_cleans_up() {
+        let system = ActorSystem::new();
+        let (stopped_tx, mut stopped_rx) = mpsc::unbounded_channel();
+        let (error_tx, mut error_rx) = mpsc::unbounded_channel();
+
+        let strategy = SupervisorStrategy::default().with_decider(move |error| {
+            if let ActorError::Panicked {
+                message_type,
+                payload,
+            } = error
+            {
+                error_tx.send((*message_type, payload.clone())).unwrap();
             }
+            Directive::Stop
+        });
+        let props = ActorProps::new(
+            move || Parent {
+                stopped: stopped_tx.clone(),
+            },
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(DefaultMailbox::new(10)),
+        )
+        .with_supervisor_strategy(strategy);
+        let parent = system.spawn_props("parent", props).await.unwrap();
+        let child = parent.ask(SpawnChild).await.unwrap();
+
+        assert!(child.ask(Panic).await.is_err());
+
+        let (message_type, payload) = error_rx.recv().await.unwrap();
+        assert_eq!(message_type, Some(std::any::type_name::<Panic>()));
+        assert_eq!(payload, "boom");
+
+        stopped_rx.recv().await.unwrap();
+        assert!(system.get::<Child>(child.path()).await.is_none());
+    }
+
+    struct Session {
+        stopped: mpsc::UnboundedSender<()>,
+    }
+
+    #[async_trait]
+    impl Actor for Session {
+        async fn started(&mut self, ctx: &mut ActorContext) -> Result<()> {
+            ctx.set_receive_timeout(std::time::Duration::from_millis(20));
+            Ok(())
         }
+
+        async fn idle(&mut self, _ctx: &mut ActorContext) -> MessageHandlerResult {
+            MessageHandlerResult::Stop {
+                reason: "idle".into(),
+            }
+        }
+
+        async fn stopped(&mut self, _ctx: &mut ActorContext) {
+            self.stopped.send(()).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn idle_actor_is_passivated() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let session = system
+            .spawn(
+                "session",
+                move || Session {
+                    stopped: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+
+        rx.recv().await.unwrap();
+        assert!(session.is_closed());
+    }
+
+    struct Recorder {
+        log: mpsc::UnboundedSender<u32>,
+    }
+
+    #[async_trait]
+    impl Actor for Recorder {}
+
+    /// Blocks the actor until the `Notify` is notified, the sender is told
+    /// once it is blocked.
+    struct Block(std::sync::Arc<tokio::sync::Notify>, oneshot::Sender<()>);
+    impl Message for Block {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Block> for Recorder {
+        async fn handle(&mut self, msg: Block, _ctx: &mut ActorContext) {
+            let _ = msg.1.send(());
+            msg.0.notified().await;
+        }
+    }
+
+    /// Blocks `recorder` and returns what unblocks it.
+    async fn block(recorder: &ActorRef<Recorder>) -> std::sync::Arc<tokio::sync::Notify> {
+        let release = std::sync::Arc::new(tokio::sync::Notify::new());
+        let (blocked, is_blocked) = oneshot::channel();
+        recorder
+            .tell(Block(release.clone(), blocked))
+            .await
+            .unwrap();
+        is_blocked.await.unwrap();
+        release
+    }
+
+    struct Item(u32, Priority);
+    impl Message for Item {
+        type Response = ();
+
+        fn priority(&self) -> Priority {
+            self.1
+        }
+    }
+
+    #[async_trait]
+    impl Handler<Item> for Recorder {
+        async fn handle(&mut self, msg: Item, _ctx: &mut ActorContext) {
+            self.log.send(msg.0).unwrap();
+        }
+    }
+
+    async fn spawn_recorder(
+        system: &ActorSystem,
+        buffer: usize,
+    ) -> (ActorRef<Recorder>, mpsc::UnboundedReceiver<u32>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let props = ActorProps::new(
+            move || Recorder { log: tx.clone() },
+            || Box::new(DefaultActorSpawner::new()),
+            move || Box::new(PriorityMailbox::new(buffer)),
+        );
+        (system.spawn_props("recorder", props).await.unwrap(), rx)
+    }
+
+    #[tokio::test]
+    async fn priority_mailbox_handles_higher_priority_first() {
+        let system = ActorSystem::new();
+        let (recorder, mut rx) = spawn_recorder(&system, 10).await;
+
+        let release = block(&recorder).await;
+        recorder.tell(Item(1, Priority::Low)).await.unwrap();
+        recorder.tell(Item(2, Priority::Normal)).await.unwrap();
+        recorder.tell(Item(3, Priority::High)).await.unwrap();
+        recorder.tell(Item(4, Priority::High)).await.unwrap();
+        release.notify_one();
+
+        let mut log = Vec::new();
+        for _ in 0..4 {
+            log.push(rx.recv().await.unwrap());
+        }
+        assert_eq!(log, vec![3, 4, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn poison_skips_queued_messages() {
+        let system = ActorSystem::new();
+        let (recorder, mut rx) = spawn_recorder(&system, 1).await;
+
+        let release = block(&recorder).await;
+        recorder.tell(Item(1, Priority::High)).await.unwrap();
+
+        // the mailbox is full, but the control lane isn't
+        recorder.untyped().poison().await.unwrap();
+        release.notify_one();
+
+        assert!(rx.recv().await.is_none());
+        assert!(recorder.is_closed());
+    }
+
+    #[tokio::test]
+    async fn fair_throughput_checks_system_messages_between_batches() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let props = ActorProps::new(
+            move || Recorder { log: tx.clone() },
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(DefaultMailbox::new(100)),
+        )
+        .with_throughput(Throughput::new(2).with_fairness());
+        let recorder = system.spawn_props("recorder", props).await.unwrap();
+
+        let release = block(&recorder).await;
+        for i in 0..50 {
+            recorder.tell(Item(i, Priority::Normal)).await.unwrap();
+        }
+        recorder.untyped().poison().await.unwrap();
+        release.notify_one();
+
+        let mut handled = 0;
+        while rx.recv().await.is_some() {
+            handled += 1;
+        }
+        assert!(handled < 2);
+    }
+
+    struct Spinner;
+    #[async_trait]
+    impl Actor for Spinner {}
+
+    struct Spin;
+    impl Message for Spin {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Spin> for Spinner {
+        async fn handle(&mut self, _msg: Spin, ctx: &mut ActorContext) {
+            let _ = ctx.myself::<Self>().try_tell(Spin);
+        }
+    }
+
+    #[tokio::test]
+    async fn busy_actor_yields_to_others() {
+        let system = ActorSystem::new();
+        let spinner = system.spawn("spinner", || Spinner, 10).await.unwrap();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let child = system
+            .spawn(
+                "child",
+                move || Child {
+                    stopped: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+
+        spinner.tell(Spin).await.unwrap();
+        let pong = child
+            .ask_timeout(Ping, std::time::Duration::from_secs(1))
+            .await;
+        assert!(pong.is_ok());
+        spinner.poison().await.unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "mailbox capacity must be at least 1")]
+    fn zero_capacity_mailboxes_are_rejected() {
+        let _ = PriorityMailbox::<Recorder>::new(0);
     }
 }
@@ -0,0 +1,352 @@
+use std::{fmt::Display, ops::Deref, str::FromStr};
+
+use thiserror::Error;
+
+const SCHEME: &str = "actor://";
+/// Segment all user actors live under in the URI form.
+const USER_ROOT: &str = "user";
+const DEFAULT_SYSTEM: &str = "default";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    #[error("Actor path is empty")]
+    Empty,
+
+    #[error("Actor path has an empty segment")]
+    EmptySegment,
+
+    #[error("Illegal actor name")]
+    IllegalName(String),
+
+    #[error("Reserved actor name")]
+    ReservedName(String),
+
+    #[error("Invalid actor system address")]
+    InvalidAddress(String),
+
+    #[error("Actor paths must start at /user")]
+    InvalidRoot(String),
+}
+
+/// Where an actor system lives, its name and for remote systems host and port.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Address {
+    system: String,
+    host: Option<(String, u16)>,
+}
+
+impl Address {
+    pub fn local(system: &str) -> Result<Self, PathError> {
+        let valid = !system.is_empty()
+            && system
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(PathError::InvalidAddress(system.into()));
+        }
+
+        Ok(Self {
+            system: system.into(),
+            host: None,
+        })
+    }
+
+    pub fn remote(system: &str, host: &str, port: u16) -> Result<Self, PathError> {
+        let valid = !host.is_empty() && !host.contains(['/', '@', ':']);
+        if !valid {
+            return Err(PathError::InvalidAddress(host.into()));
+        }
+
+        let mut address = Self::local(system)?;
+        address.host = Some((host.into(), port));
+        Ok(address)
+    }
+
+    pub fn system(&self) -> &str {
+        &self.system
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_ref().map(|(host, _)| host.as_str())
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        self.host.as_ref().map(|(_, port)| *port)
+    }
+}
+
+impl Default for Address {
+    fn default() -> Self {
+        Self {
+            system: DEFAULT_SYSTEM.into(),
+            host: None,
+        }
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{SCHEME}{}", self.system)?;
+        if let Some((host, port)) = &self.host {
+            write!(f, "@{host}:{port}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Address {
+    type Err = PathError;
+
+    /// Parses `actor://system` or `actor://system@host:port`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PathError::InvalidAddress(s.into());
+        let authority = s.strip_prefix(SCHEME).ok_or_else(invalid)?;
+        let Some((system, host)) = authority.split_once('@') else {
+            return Self::local(authority);
+        };
+
+        let (host, port) = host.rsplit_once(':').ok_or_else(invalid)?;
+        let port = port.parse().map_err(|_| invalid())?;
+        Self::remote(system, host, port)
+    }
+}
+
+/// Validated path of an actor: the address of its system and the names from
+/// the top level actor down to it. Its canonical form is a URI like
+/// `actor://system@host:port/user/a/b`, `FromStr` also accepts `/user/a/b`
+/// and `a/b` for actors of the default local system.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ActorPath {
+    address: Address,
+    segments: Vec<String>,
+    /// The segments joined with `/`, the form paths had before they were
+    /// validated. Only kept for the deprecated `Deref` and `AsRef` impls.
+    joined: String,
+}
+
+impl ActorPath {
+    /// Parses `path`, see `FromStr`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` is not a valid actor path.
+    #[deprecated(note = "use `str::parse`, which returns an error for invalid paths")]
+    pub fn new<T: Into<String>>(path: T) -> Self {
+        let path = path.into();
+        path.parse()
+            .unwrap_or_else(|e| panic!("invalid actor path {path:?}: {e}"))
+    }
+
+    /// The root all top level actors of a system are children of.
+    pub fn root(address: Address) -> Self {
+        Self::with_segments(address, Vec::new())
+    }
+
+    fn with_segments(address: Address, segments: Vec<String>) -> Self {
+        let joined = segments.join("/");
+        Self {
+            address,
+            segments,
+            joined,
+        }
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Names from the top level actor down to this one, empty for the root.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn name(&self) -> &str {
+        self.segments.last().map_or(USER_ROOT, String::as_str)
+    }
+
+    /// Whether this is the path of a child actor, top level actors have none.
+    pub fn has_parent(&self) -> bool {
+        self.segments.len() > 1
+    }
+
+    pub fn parent(&self) -> Option<ActorPath> {
+        if !self.has_parent() {
+            return None;
+        }
+
+        let segments = self.segments[..self.segments.len() - 1].to_vec();
+        Some(Self::with_segments(self.address.clone(), segments))
+    }
+
+    /// Path of the child `name`. Names starting with `$` are reserved for
+    /// generated names, see `ActorSystem::spawn_anonymous`.
+    pub fn child(&self, name: &str) -> Result<ActorPath, PathError> {
+        if name.starts_with('$') {
+            return Err(PathError::ReservedName(name.into()));
+        }
+        self.generated_child(name)
+    }
+
+    /// Like `child`, but allows reserved names.
+    pub(crate) fn generated_child(&self, name: &str) -> Result<ActorPath, PathError> {
+        validate_name(name)?;
+
+        let mut segments = self.segments.clone();
+        segments.push(name.into());
+        Ok(Self::with_segments(self.address.clone(), segments))
+    }
+
+    /// Path of the descendant at the relative path `path`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a name in `path` is not valid.
+    #[deprecated(note = "use `child`, which returns an error for invalid names")]
+    pub fn join<T: Into<String>>(&self, path: T) -> ActorPath {
+        let path = path.into();
+        path.split('/')
+            .try_fold(self.clone(), |parent, name| parent.child(name))
+            .unwrap_or_else(|e| panic!("invalid actor path {path:?}: {e}"))
+    }
+}
+
+/// Deprecated: paths used to be plain strings, use `segments` or `Display`
+/// instead. Derefs to the segments joined with `/`, without the address.
+impl Deref for ActorPath {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.joined
+    }
+}
+
+/// Deprecated like the `Deref` impl.
+impl AsRef<String> for ActorPath {
+    fn as_ref(&self) -> &String {
+        &self.joined
+    }
+}
+
+impl Display for ActorPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{USER_ROOT}", self.address)?;
+        for segment in &self.segments {
+            write!(f, "/{segment}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ActorPath {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(PathError::Empty);
+        }
+
+        let (address, path) = match s.strip_prefix(SCHEME) {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                let address = s[..SCHEME.len() + end].parse()?;
+                (address, rest[end..].strip_prefix('/').unwrap_or_default())
+            }
+            None => (Address::default(), s),
+        };
+
+        // `a/b` is relative to the root, `/user/a/b` and URIs are absolute
+        let relative = s.strip_prefix(SCHEME).is_none() && !path.starts_with('/');
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let path = if relative {
+            path
+        } else {
+            match path.split_once('/') {
+                Some((USER_ROOT, rest)) => rest,
+                None if path == USER_ROOT => "",
+                _ => return Err(PathError::InvalidRoot(s.into())),
+            }
+        };
+
+        let mut actor_path = Self::root(address);
+        if !path.is_empty() {
+            for name in path.split('/') {
+                actor_path = actor_path.generated_child(name)?;
+            }
+        }
+        Ok(actor_path)
+    }
+}
+
+/// Names can't contain `/` or the wildcards of `ActorSelection`.
+fn validate_name(name: &str) -> Result<(), PathError> {
+    if name.is_empty() {
+        return Err(PathError::EmptySegment);
+    }
+    if name == "." || name == ".." {
+        return Err(PathError::ReservedName(name.into()));
+    }
+
+    let legal = |c: char| c.is_ascii_alphanumeric() || "-_.~+=:@&!,;'()$".contains(c);
+    if !name.chars().all(legal) {
+        return Err(PathError::IllegalName(name.into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_round_trip() {
+        let path: ActorPath = "actor://orders@10.0.0.1:2552/user/a/b".parse().unwrap();
+        assert_eq!(path.address().system(), "orders");
+        assert_eq!(path.address().host(), Some("10.0.0.1"));
+        assert_eq!(path.address().port(), Some(2552));
+        assert_eq!(path.segments(), ["a", "b"]);
+        assert_eq!(path.to_string().parse::<ActorPath>().unwrap(), path);
+
+        let local: ActorPath = "a/b".parse().unwrap();
+        assert_eq!(local.to_string(), "actor://default/user/a/b");
+        assert_eq!("/user/a/b".parse(), Ok(local.clone()));
+        assert_eq!(local.parent(), "a".parse().ok());
+        assert_eq!("/user".parse(), Ok(ActorPath::root(Address::default())));
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let local: ActorPath = "a".parse().unwrap();
+        assert_eq!("".parse::<ActorPath>(), Err(PathError::Empty));
+        assert_eq!("a//b".parse::<ActorPath>(), Err(PathError::EmptySegment));
+        assert!(matches!(
+            "a/b*".parse::<ActorPath>(),
+            Err(PathError::IllegalName(_))
+        ));
+        assert!(matches!(
+            "a/..".parse::<ActorPath>(),
+            Err(PathError::ReservedName(_))
+        ));
+        assert!(matches!(local.child("$a"), Err(PathError::ReservedName(_))));
+        assert!(matches!(
+            "/system/a".parse::<ActorPath>(),
+            Err(PathError::InvalidRoot(_))
+        ));
+        assert!(matches!(
+            "actor://bad name/user".parse::<ActorPath>(),
+            Err(PathError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_string_api() {
+        let path = ActorPath::new("a").join("b/c");
+        assert_eq!(path.segments(), ["a", "b", "c"]);
+        assert_eq!(path.as_str(), "a/b/c");
+        assert_eq!(path.as_ref(), "a/b/c");
+    }
+}
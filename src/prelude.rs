@@ -1,5 +1,5 @@
 #[allow(unused_imports)]
 pub use crate::{
     Actor, ActorContext, ActorProps, ActorRef, ActorSystem, DefaultActorSpawner, DefaultMailbox,
-    Handler, Message, Result,
+    Handler, Message, Priority, PriorityMailbox, Recipient, Result, ServiceKey, Terminated,
 };
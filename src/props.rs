@@ -1,24 +1,37 @@
-use std::marker::PhantomData;
+use std::collections::HashSet;
 
-use crate::{Actor, ActorContext, ActorPath, ActorRef, ActorSpawner, ActorSystem, Mailbox};
+use tokio::sync::watch;
+
+use crate::{
+    Actor, ActorContext, ActorPath, ActorRef, ActorRunner, ActorSpawner, ActorSystem, Interceptor,
+    LifecycleEventKind, Mailbox, SupervisorStrategy, Throughput, UntypedActorRef,
+    interceptor::Interceptors, registry::ActorEntry,
+};
+
+const DEFAULT_STASH_CAPACITY: usize = 1000;
 
 #[derive(Debug)]
 pub struct ActorProps<A, F, S, M>
 where
     A: Actor,
-    F: Fn() -> A,
+    F: Fn() -> A + Send + Sync + 'static,
     S: Fn() -> Box<dyn ActorSpawner<A>>,
     M: Fn() -> Box<dyn Mailbox<A>>,
 {
     actor_fn: F,
     spawner_fn: S,
     mailbox_fn: M,
+    supervisor_strategy: SupervisorStrategy,
+    max_children: Option<usize>,
+    stash_capacity: usize,
+    throughput: Throughput,
+    interceptors: Interceptors,
 }
 
 impl<A, F, S, M> ActorProps<A, F, S, M>
 where
     A: Actor,
-    F: Fn() -> A,
+    F: Fn() -> A + Send + Sync + 'static,
     S: Fn() -> Box<dyn ActorSpawner<A>>,
     M: Fn() -> Box<dyn Mailbox<A>>,
 {
@@ -27,9 +40,45 @@ where
             actor_fn,
             spawner_fn,
             mailbox_fn,
+            supervisor_strategy: SupervisorStrategy::default(),
+            max_children: None,
+            stash_capacity: DEFAULT_STASH_CAPACITY,
+            throughput: Throughput::default(),
+            interceptors: Interceptors::default(),
         }
     }
 
+    /// Sets the strategy used to supervise the children of the actor.
+    pub fn with_supervisor_strategy(mut self, strategy: SupervisorStrategy) -> Self {
+        self.supervisor_strategy = strategy;
+        self
+    }
+
+    /// Limits how many messages the actor can stash, see `ActorContext::stash`.
+    pub fn with_stash_capacity(mut self, capacity: usize) -> Self {
+        self.stash_capacity = capacity;
+        self
+    }
+
+    /// Sets how many messages the actor handles before it lets other tasks run.
+    pub fn with_throughput(mut self, throughput: Throughput) -> Self {
+        self.throughput = throughput;
+        self
+    }
+
+    /// Adds `interceptor` to the chain wrapping every message the actor
+    /// handles, after the system's default interceptors.
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor) -> Self {
+        self.interceptors = self.interceptors.with(interceptor);
+        self
+    }
+
+    /// Limits how many children the actor can have at the same time.
+    pub fn with_max_children(mut self, max_children: usize) -> Self {
+        self.max_children = Some(max_children);
+        self
+    }
+
     pub fn new_actor(&self) -> A {
         (self.actor_fn)()
     }
@@ -42,18 +91,52 @@ where
         (self.mailbox_fn)()
     }
 
-    pub(crate) fn spawn(&mut self, system: ActorSystem, path: ActorPath) -> ActorRef<A> {
-        let ctx = ActorContext {
-            path: path.clone(),
+    pub(crate) fn spawn(
+        self,
+        system: ActorSystem,
+        path: ActorPath,
+        parent: Option<UntypedActorRef>,
+    ) -> (ActorRef<A>, ActorEntry) {
+        let mut mailbox = self.new_mailbox();
+        let spawner = self.new_spawner();
+
+        let mut actor_ref = ActorRef::new(path.clone(), mailbox.take_sender());
+        actor_ref.control = mailbox.take_control_sender();
+        actor_ref.ask_timeout = system.default_ask_timeout();
+        actor_ref.dead_letters = Some(system.dead_letters.clone());
+        let interceptors = system.interceptors.then(&self.interceptors);
+        let mut ctx = ActorContext::new(
+            path,
             system,
-            _private: PhantomData,
-        };
+            actor_ref.untyped(),
+            parent,
+            self.supervisor_strategy,
+            self.stash_capacity,
+            self.throughput,
+        );
+        ctx.interceptors = interceptors;
+        let watchers = ctx.watchers.clone();
 
-        let actor = self.new_actor();
-        let mailbox = self.new_mailbox();
-        let spawner = self.new_spawner();
+        // before the runner can publish anything
+        let spawned = LifecycleEventKind::Spawned;
+        let lifecycle = &ctx.system.lifecycle;
+        lifecycle.publish::<A>(&ctx.path, actor_ref.uid(), spawned);
+
+        let (terminated_tx, terminated) = watch::channel(());
+        let runner = ActorRunner::new(ctx, mailbox, Box::new(self.actor_fn), terminated_tx);
+        let handle = spawner.spawn(runner);
+
+        let entry = ActorEntry {
+            actor_ref: actor_ref.untyped(),
+            actor_type: std::any::type_name::<A>(),
+            terminated,
+            abort_handle: handle.abort_handle(),
+            max_children: self.max_children,
+            children: HashSet::new(),
+            watchers,
+        };
 
-        spawner.spawn(ctx, actor, mailbox)
+        (actor_ref, entry)
     }
 }
 
@@ -72,12 +155,12 @@ mod tests {
     async fn test1() {
         let system = ActorSystem::new();
 
-        let mut sut = ActorProps::new(
+        let sut = ActorProps::new(
             || TestActor,
             || Box::new(DefaultActorSpawner::<TestActor>::new()),
             || Box::new(DefaultMailbox::<TestActor>::new(10)),
         );
 
-        sut.spawn(system, ActorPath::new("test"));
+        sut.spawn(system, "test".parse().unwrap(), None);
     }
 }
@@ -0,0 +1,247 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Display,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::watch;
+
+use crate::{Message, Recipient, UntypedActorRef};
+
+/// Name of a service whose providers handle `M`. Keys with the same id but
+/// different message types name different services.
+pub struct ServiceKey<M: Message> {
+    id: String,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<M: Message> ServiceKey<M> {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            _message: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl<M: Message> Clone for ServiceKey<M> {
+    fn clone(&self) -> Self {
+        Self::new(self.id.clone())
+    }
+}
+
+impl<M: Message> Display for ServiceKey<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+impl<M: Message> std::fmt::Debug for ServiceKey<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceKey")
+            .field("id", &self.id)
+            .field("message_type", &std::any::type_name::<M>())
+            .finish()
+    }
+}
+
+/// Registered providers of a service, in the order they registered.
+type Listing<M> = watch::Sender<Vec<Recipient<M>>>;
+
+/// Type-erased `Listing` so services of all message types fit in one map.
+trait Service: Send + Sync {
+    /// Removes the providers that are references to `actor`.
+    fn remove(&self, actor: &UntypedActorRef);
+
+    /// Whether the listing has neither providers nor subscribers.
+    fn is_unused(&self) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<M: Message> Service for Listing<M> {
+    fn remove(&self, actor: &UntypedActorRef) {
+        self.send_if_modified(|recipients| {
+            let len = recipients.len();
+            recipients.retain(|recipient| !is_ref_to(recipient, actor));
+            recipients.len() != len
+        });
+    }
+
+    fn is_unused(&self) -> bool {
+        self.borrow().is_empty() && self.receiver_count() == 0
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Listings by key id and message type.
+type Services = HashMap<(String, TypeId), Box<dyn Service>>;
+
+fn is_ref_to<M: Message>(recipient: &Recipient<M>, actor: &UntypedActorRef) -> bool {
+    recipient.uid() == actor.uid() && recipient.path() == actor.path()
+}
+
+fn is_same<M: Message>(a: &Recipient<M>, b: &Recipient<M>) -> bool {
+    a.uid() == b.uid() && a.path() == b.path()
+}
+
+/// Finds actors by the services they provide instead of by path, see
+/// `ActorSystem::receptionist`. Providers are deregistered when they stop.
+#[derive(Clone, Default)]
+pub struct Receptionist {
+    services: Arc<Mutex<Services>>,
+}
+
+impl Receptionist {
+    /// Registers `recipient` as a provider of `key`, unless it already is one.
+    pub fn register<M: Message>(&self, key: &ServiceKey<M>, recipient: Recipient<M>) {
+        self.with_listing(key, |listing| {
+            listing.send_if_modified(|recipients| {
+                // an actor that stopped already would never be deregistered
+                let add =
+                    !recipient.is_closed() && !recipients.iter().any(|r| is_same(r, &recipient));
+                if add {
+                    recipients.push(recipient);
+                }
+                add
+            });
+        });
+    }
+
+    pub fn deregister<M: Message>(&self, key: &ServiceKey<M>, recipient: &Recipient<M>) {
+        let mut services = self.services.lock().unwrap();
+        let id = (key.id.clone(), TypeId::of::<M>());
+        let Some(service) = services.get(&id) else {
+            return;
+        };
+
+        let listing: &Listing<M> = service.as_any().downcast_ref().unwrap();
+        listing.send_if_modified(|recipients| {
+            let len = recipients.len();
+            recipients.retain(|r| !is_same(r, recipient));
+            recipients.len() != len
+        });
+        if service.is_unused() {
+            services.remove(&id);
+        }
+    }
+
+    /// Current providers of `key`.
+    pub fn find<M: Message>(&self, key: &ServiceKey<M>) -> Vec<Recipient<M>> {
+        let services = self.services.lock().unwrap();
+        services
+            .get(&(key.id.clone(), TypeId::of::<M>()))
+            .and_then(|service| service.as_any().downcast_ref::<Listing<M>>())
+            .map(|listing| listing.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    /// Follows the providers of `key`, starting with the current ones.
+    pub fn subscribe<M: Message>(&self, key: &ServiceKey<M>) -> watch::Receiver<Vec<Recipient<M>>> {
+        self.with_listing(key, |listing| listing.subscribe())
+    }
+
+    /// Deregisters `actor` from every service, dropping the listings nobody
+    /// provides or follows anymore.
+    pub(crate) fn remove_actor(&self, actor: &UntypedActorRef) {
+        let mut services = self.services.lock().unwrap();
+        services.retain(|_, service| {
+            service.remove(actor);
+            !service.is_unused()
+        });
+    }
+
+    /// Runs `f` on the listing of `key`, creating it if there is none.
+    fn with_listing<M: Message, T>(
+        &self,
+        key: &ServiceKey<M>,
+        f: impl FnOnce(&Listing<M>) -> T,
+    ) -> T {
+        let mut services = self.services.lock().unwrap();
+        let service = services
+            .entry((key.id.clone(), TypeId::of::<M>()))
+            .or_insert_with(|| Box::new(Listing::<M>::new(Vec::new())));
+        f(service.as_any().downcast_ref().unwrap())
+    }
+}
+
+impl std::fmt::Debug for Receptionist {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receptionist")
+            .field("services", &self.services.lock().unwrap().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorPath, prelude::*};
+    use async_trait::async_trait;
+
+    struct Work;
+    impl Message for Work {
+        type Response = ActorPath;
+    }
+
+    fn workers() -> ServiceKey<Work> {
+        ServiceKey::new("workers")
+    }
+
+    struct Worker;
+
+    #[async_trait]
+    impl Actor for Worker {
+        async fn started(&mut self, ctx: &mut ActorContext) -> Result<()> {
+            ctx.register::<Self, _>(&workers())
+        }
+    }
+
+    #[async_trait]
+    impl Handler<Work> for Worker {
+        async fn handle(&mut self, _msg: Work, ctx: &mut ActorContext) -> ActorPath {
+            ctx.path.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn providers_are_found_until_they_stop() {
+        let system = ActorSystem::new();
+        let receptionist = system.receptionist();
+        let mut listing = receptionist.subscribe(&workers());
+        assert!(listing.borrow_and_update().is_empty());
+
+        let first = system.spawn("first", || Worker, 10).await.unwrap();
+        let second = system.spawn("second", || Worker, 10).await.unwrap();
+        listing
+            .wait_for(|workers| workers.len() == 2)
+            .await
+            .unwrap();
+
+        let mut paths = Vec::new();
+        for worker in receptionist.find(&workers()) {
+            paths.push(worker.ask(Work).await.unwrap());
+        }
+        paths.sort();
+        assert_eq!(paths, [first.path().clone(), second.path().clone()]);
+        assert!(
+            receptionist
+                .find(&ServiceKey::<Work>::new("idlers"))
+                .is_empty()
+        );
+        assert_eq!(receptionist.services.lock().unwrap().len(), 1);
+
+        first.poison().await.unwrap();
+        let listing = listing.wait_for(|workers| workers.len() == 1).await;
+        assert_eq!(listing.unwrap()[0].path(), second.path());
+    }
+}
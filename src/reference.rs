@@ -1,82 +1,72 @@
-use std::{fmt::Display, ops::Deref};
+use async_trait::async_trait;
+use std::{
+    any::Any,
+    hash::{Hash, Hasher},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
 use tokio::sync::oneshot;
 
 use crate::{
-    Actor, ActorError, BoxedMessageHandler, Handler, Message, Result, Sender,
+    Actor, ActorError, ActorPath, BoxedMessageHandler, ControlSender, DeadLetterReason, Handler,
+    Message, Result, Sender,
+    channel::SendError,
+    dead_letters::DeadLetters,
     handler::{Envelope, SystemEnvelope, SystemHandler},
     system::SystemMessage,
 };
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-pub struct ActorPath {
-    inner: String,
-}
-
-impl ActorPath {
-    pub fn new<T: Into<String>>(path: T) -> Self {
-        Self { inner: path.into() }
-    }
-
-    pub fn name(&self) -> &str {
-        let parts: Vec<&str> = self.inner.split('/').collect();
-        parts.last().unwrap()
-    }
-
-    pub fn has_parent(&self) -> bool {
-        self.inner.contains("/")
-    }
-
-    pub fn parent(&self) -> Option<ActorPath> {
-        let parts: Vec<&str> = self.inner.split('/').collect();
-        if parts.len() > 1 {
-            let inner = parts[0..parts.len() - 1].join("/");
-            Some(ActorPath { inner })
-        } else {
-            None
-        }
-    }
-
-    pub fn join<T: Into<String>>(&self, path: T) -> ActorPath {
-        let new_path = format!("{}/{}", self.as_ref(), path.into());
-        ActorPath::new(new_path)
-    }
-}
-
-impl Display for ActorPath {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.inner)
-    }
-}
-
-impl AsRef<String> for ActorPath {
-    #[inline]
-    fn as_ref(&self) -> &String {
-        &self.inner
-    }
-}
+/// Source of incarnation ids, unique within the process so references to
+/// actors spawned at the same path one after another stay distinguishable.
+static NEXT_UID: AtomicU64 = AtomicU64::new(1);
 
-impl Deref for ActorPath {
-    type Target = String;
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
-}
+type SendResult<A> =
+    std::result::Result<Option<BoxedMessageHandler<A>>, SendError<BoxedMessageHandler<A>>>;
 
 #[derive(Debug)]
 pub struct ActorRef<A: Actor> {
     path: ActorPath,
+    uid: u64,
     sender: Sender<A>,
+    /// Control lane of the mailbox, if it has one.
+    pub(crate) control: Option<ControlSender<A>>,
+    pub(crate) ask_timeout: Option<Duration>,
+    /// Where undeliverable messages are published, set for spawned actors.
+    pub(crate) dead_letters: Option<DeadLetters>,
 }
 
 impl<A: Actor> ActorRef<A> {
     pub fn new(path: ActorPath, sender: Sender<A>) -> Self {
-        ActorRef { path, sender }
+        ActorRef {
+            path,
+            uid: NEXT_UID.fetch_add(1, Ordering::Relaxed),
+            sender,
+            control: None,
+            ask_timeout: None,
+            dead_letters: None,
+        }
+    }
+
+    /// Returns a reference whose `ask` gives up after `timeout`.
+    pub fn with_ask_timeout(mut self, timeout: Duration) -> Self {
+        self.ask_timeout = Some(timeout);
+        self
     }
 
     pub fn path(&self) -> &ActorPath {
         &self.path
     }
+
+    /// Incarnation of the actor, different for every actor spawned at the
+    /// same path.
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+
     pub async fn tell<M>(&self, msg: M) -> Result<()>
     where
         M: Message,
@@ -87,7 +77,61 @@ impl<A: Actor> ActorRef<A> {
         Ok(())
     }
 
+    /// Sends `msg` without waiting for room in the mailbox. Fails with
+    /// `ActorError::MailboxFull` if the mailbox is full and would apply
+    /// backpressure.
+    pub fn try_tell<M>(&self, msg: M) -> Result<()>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        let envelope = Envelope::new(msg, None);
+        let result = self.sender.try_send(Box::new(envelope));
+        self.delivered(result)
+    }
+
+    /// Sends `msg`, waiting at most `timeout` for room in the mailbox.
+    pub async fn tell_timeout<M>(&self, msg: M, timeout: Duration) -> Result<()>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        tokio::time::timeout(timeout, self.tell(msg))
+            .await
+            .map_err(|_| ActorError::Timeout {
+                path: self.path.clone(),
+                message_type: std::any::type_name::<M>(),
+            })?
+    }
+
+    /// Sends `msg` and waits for the response, for at most the ask timeout
+    /// of this reference if it has one. Without one it waits as long as the
+    /// actor takes, forever if it never replies, see `ask_timeout`.
     pub async fn ask<M>(&self, msg: M) -> Result<M::Response>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        match self.ask_timeout {
+            Some(timeout) => self.ask_timeout(msg, timeout).await,
+            None => self.ask_unbounded(msg).await,
+        }
+    }
+
+    pub async fn ask_timeout<M>(&self, msg: M, timeout: Duration) -> Result<M::Response>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        tokio::time::timeout(timeout, self.ask_unbounded(msg))
+            .await
+            .map_err(|_| ActorError::Timeout {
+                path: self.path.clone(),
+                message_type: std::any::type_name::<M>(),
+            })?
+    }
+
+    async fn ask_unbounded<M>(&self, msg: M) -> Result<M::Response>
     where
         M: Message,
         A: Handler<M>,
@@ -97,7 +141,7 @@ impl<A: Actor> ActorRef<A> {
         self.send(Box::new(envelope)).await?;
         reply_receiver
             .await
-            .map_err(|e| ActorError::SendError(e.to_string()))
+            .map_err(|e| ActorError::SendError(e.to_string()))?
     }
 
     pub async fn poison(&self) -> Result<()> {
@@ -112,43 +156,444 @@ impl<A: Actor> ActorRef<A> {
     {
         let (reply_sender, reply_receiver) = oneshot::channel();
         let envelope = SystemEnvelope::new(msg, Some(reply_sender));
-        self.send(Box::new(envelope)).await?;
+        self.send_control(Box::new(envelope)).await?;
         reply_receiver
             .await
             .map_err(|e| ActorError::SendError(e.to_string()))
     }
 
-    #[allow(dead_code)]
     pub(crate) async fn sys_tell<M>(&self, msg: M) -> Result<()>
     where
         M: Message,
         A: SystemHandler<M>,
     {
         let envelope = SystemEnvelope::new(msg, None);
-        self.send(Box::new(envelope)).await?;
+        self.send_control(Box::new(envelope)).await?;
         Ok(())
     }
 
     #[inline]
-    async fn send(&self, msg: BoxedMessageHandler<A>) -> Result<()> {
-        self.sender
-            .send(msg)
-            .await
-            .map_err(|e| ActorError::SendError(e.to_string()))?;
+    pub(crate) async fn send(&self, msg: BoxedMessageHandler<A>) -> Result<()> {
+        let result = self.sender.send(msg).await;
+        self.delivered(result)
+    }
 
-        Ok(())
+    /// Publishes dropped and undeliverable messages as dead letters.
+    fn delivered(&self, result: SendResult<A>) -> Result<()> {
+        match result {
+            Ok(None) => Ok(()),
+            Ok(Some(dropped)) => {
+                self.dead_letter(&dropped, DeadLetterReason::Dropped);
+                Ok(())
+            }
+            Err(SendError::Closed(msg)) => {
+                self.dead_letter(&msg, DeadLetterReason::MailboxClosed);
+                Err(self.send_error(SendError::Closed(msg)))
+            }
+            Err(error) => Err(self.send_error(error)),
+        }
+    }
+
+    fn dead_letter(&self, msg: &BoxedMessageHandler<A>, reason: DeadLetterReason) {
+        if let Some(dead_letters) = &self.dead_letters {
+            dead_letters.publish(self.path.clone(), self.uid, msg.message_type(), reason);
+        }
+    }
+
+    fn send_error(&self, error: SendError<BoxedMessageHandler<A>>) -> ActorError {
+        match error {
+            SendError::Closed(_) => ActorError::SendError("channel closed".into()),
+            SendError::Full(_) => ActorError::MailboxFull(self.path.clone()),
+        }
+    }
+
+    /// Sends through the control lane, falling back to the regular one
+    /// without being subject to its capacity.
+    async fn send_control(&self, msg: BoxedMessageHandler<A>) -> Result<()> {
+        match &self.control {
+            Some(control) => control
+                .send(msg)
+                .map_err(|e| ActorError::SendError(e.to_string())),
+            None => self.sender.force_send(msg).map_err(|e| self.send_error(e)),
+        }
     }
 
     pub fn is_closed(&self) -> bool {
         self.sender.is_closed()
     }
+
+    /// Returns a reference that can only send `M`, but doesn't depend on the
+    /// type of the actor.
+    pub fn recipient<M>(&self) -> Recipient<M>
+    where
+        M: Message,
+        A: Handler<M>,
+    {
+        Recipient {
+            path: self.path.clone(),
+            uid: self.uid,
+            inner: Arc::new(self.clone()),
+        }
+    }
+
+    pub fn untyped(&self) -> UntypedActorRef {
+        UntypedActorRef {
+            path: self.path.clone(),
+            uid: self.uid,
+            inner: Arc::new(self.clone()),
+        }
+    }
 }
 
 impl<A: Actor> Clone for ActorRef<A> {
     fn clone(&self) -> Self {
         Self {
             path: self.path.clone(),
+            uid: self.uid,
             sender: self.sender.clone(),
+            control: self.control.clone(),
+            ask_timeout: self.ask_timeout,
+            dead_letters: self.dead_letters.clone(),
         }
     }
 }
+
+impl<A: Actor> PartialEq for ActorRef<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.uid == other.uid && self.path == other.path
+    }
+}
+
+impl<A: Actor> Eq for ActorRef<A> {}
+
+impl<A: Actor> Hash for ActorRef<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+        self.uid.hash(state);
+    }
+}
+
+#[async_trait]
+pub(crate) trait SystemSender: Send + Sync {
+    async fn send_system(&self, msg: SystemMessage) -> Result<()>;
+
+    fn is_closed(&self) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+#[async_trait]
+impl<A: Actor> SystemSender for ActorRef<A> {
+    async fn send_system(&self, msg: SystemMessage) -> Result<()> {
+        self.sys_tell(msg).await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Reference to an actor of unknown type. It can only carry system messages,
+/// but can be turned back into a typed `ActorRef` when the type is known.
+#[derive(Clone)]
+pub struct UntypedActorRef {
+    path: ActorPath,
+    uid: u64,
+    inner: Arc<dyn SystemSender>,
+}
+
+impl UntypedActorRef {
+    pub fn path(&self) -> &ActorPath {
+        &self.path
+    }
+
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    pub fn downcast<A: Actor>(&self) -> Option<ActorRef<A>> {
+        self.inner.as_any().downcast_ref::<ActorRef<A>>().cloned()
+    }
+
+    pub async fn poison(&self) -> Result<()> {
+        self.inner.send_system(SystemMessage::Poison).await
+    }
+
+    pub(crate) async fn send_system(&self, msg: SystemMessage) -> Result<()> {
+        self.inner.send_system(msg).await
+    }
+}
+
+impl std::fmt::Debug for UntypedActorRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UntypedActorRef")
+            .field("path", &self.path)
+            .field("uid", &self.uid)
+            .finish()
+    }
+}
+
+impl PartialEq for UntypedActorRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.uid == other.uid && self.path == other.path
+    }
+}
+
+impl Eq for UntypedActorRef {}
+
+impl Hash for UntypedActorRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+        self.uid.hash(state);
+    }
+}
+
+#[async_trait]
+trait MessageSender<M: Message>: Send + Sync {
+    async fn tell(&self, msg: M) -> Result<()>;
+
+    fn try_tell(&self, msg: M) -> Result<()>;
+
+    async fn ask(&self, msg: M) -> Result<M::Response>;
+
+    async fn ask_timeout(&self, msg: M, timeout: Duration) -> Result<M::Response>;
+
+    fn is_closed(&self) -> bool;
+}
+
+#[async_trait]
+impl<M, A> MessageSender<M> for ActorRef<A>
+where
+    M: Message,
+    A: Handler<M>,
+{
+    async fn tell(&self, msg: M) -> Result<()> {
+        ActorRef::tell(self, msg).await
+    }
+
+    fn try_tell(&self, msg: M) -> Result<()> {
+        ActorRef::try_tell(self, msg)
+    }
+
+    async fn ask(&self, msg: M) -> Result<M::Response> {
+        ActorRef::ask(self, msg).await
+    }
+
+    async fn ask_timeout(&self, msg: M, timeout: Duration) -> Result<M::Response> {
+        ActorRef::ask_timeout(self, msg, timeout).await
+    }
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+/// Reference to any actor handling `M`, see `ActorRef::recipient`.
+pub struct Recipient<M: Message> {
+    path: ActorPath,
+    uid: u64,
+    inner: Arc<dyn MessageSender<M>>,
+}
+
+impl<M: Message> Recipient<M> {
+    pub fn path(&self) -> &ActorPath {
+        &self.path
+    }
+
+    pub fn uid(&self) -> u64 {
+        self.uid
+    }
+
+    pub async fn tell(&self, msg: M) -> Result<()> {
+        self.inner.tell(msg).await
+    }
+
+    pub fn try_tell(&self, msg: M) -> Result<()> {
+        self.inner.try_tell(msg)
+    }
+
+    pub async fn ask(&self, msg: M) -> Result<M::Response> {
+        self.inner.ask(msg).await
+    }
+
+    pub async fn ask_timeout(&self, msg: M, timeout: Duration) -> Result<M::Response> {
+        self.inner.ask_timeout(msg, timeout).await
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+impl<M: Message> Clone for Recipient<M> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            uid: self.uid,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<M: Message> std::fmt::Debug for Recipient<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recipient")
+            .field("path", &self.path)
+            .field("uid", &self.uid)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    struct Sleeper;
+    #[async_trait]
+    impl Actor for Sleeper {}
+
+    struct Sleep(Duration);
+    impl Message for Sleep {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Sleep> for Sleeper {
+        async fn handle(&mut self, msg: Sleep, _ctx: &mut ActorContext) {
+            tokio::time::sleep(msg.0).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn ask_timeout_expires() {
+        let system = ActorSystem::builder("default")
+            .with_default_ask_timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let sleeper = system.spawn("sleeper", || Sleeper, 10).await.unwrap();
+
+        let result = sleeper.ask(Sleep(Duration::from_millis(200))).await;
+        assert!(matches!(
+            result,
+            Err(ActorError::Timeout { ref path, message_type })
+                if path == sleeper.path() && message_type == std::any::type_name::<Sleep>()
+        ));
+
+        let result = sleeper
+            .ask_timeout(Sleep(Duration::ZERO), Duration::from_secs(1))
+            .await;
+        assert!(result.is_ok());
+    }
+
+    // paused time only advances once the sleeper is idle in its handler,
+    // which leaves room for exactly one message
+    #[tokio::test(start_paused = true)]
+    async fn try_tell_fails_on_full_mailbox() {
+        let system = ActorSystem::new();
+        let sleeper = system.spawn("sleeper", || Sleeper, 1).await.unwrap();
+
+        sleeper
+            .tell(Sleep(Duration::from_millis(200)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        sleeper.try_tell(Sleep(Duration::ZERO)).unwrap();
+
+        let result = sleeper.try_tell(Sleep(Duration::ZERO));
+        assert!(matches!(result, Err(ActorError::MailboxFull(ref path)) if path == sleeper.path()));
+
+        let result = sleeper
+            .tell_timeout(Sleep(Duration::ZERO), Duration::from_millis(10))
+            .await;
+        assert!(matches!(result, Err(ActorError::Timeout { .. })));
+    }
+
+    struct Napper;
+    #[async_trait]
+    impl Actor for Napper {}
+
+    #[async_trait]
+    impl Handler<Sleep> for Napper {
+        async fn handle(&mut self, _msg: Sleep, _ctx: &mut ActorContext) {}
+    }
+
+    #[tokio::test]
+    async fn recipients_of_different_actors() {
+        let system = ActorSystem::new();
+        let sleeper = system.spawn("sleeper", || Sleeper, 10).await.unwrap();
+        let napper = system.spawn("napper", || Napper, 10).await.unwrap();
+
+        let recipients: Vec<Recipient<Sleep>> = vec![sleeper.recipient(), napper.recipient()];
+        for recipient in &recipients {
+            recipient.tell(Sleep(Duration::ZERO)).await.unwrap();
+            recipient.ask(Sleep(Duration::ZERO)).await.unwrap();
+        }
+        assert_eq!(recipients[1].path(), napper.path());
+
+        napper.poison().await.unwrap();
+        assert!(recipients[1].is_closed());
+        assert!(recipients[1].tell(Sleep(Duration::ZERO)).await.is_err());
+    }
+
+    struct Gate;
+    #[async_trait]
+    impl Actor for Gate {}
+
+    /// Waits for a permit, signalling `entered` first.
+    struct Pass(
+        std::sync::Arc<tokio::sync::Semaphore>,
+        Option<oneshot::Sender<()>>,
+    );
+    impl Message for Pass {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Pass> for Gate {
+        async fn handle(&mut self, msg: Pass, _ctx: &mut ActorContext) {
+            if let Some(entered) = msg.1 {
+                let _ = entered.send(());
+            }
+            msg.0.acquire().await.unwrap().forget();
+        }
+    }
+
+    #[tokio::test]
+    async fn system_messages_reach_full_failing_mailbox() {
+        let system = ActorSystem::new();
+        let props = ActorProps::new(
+            || Gate,
+            || Box::new(DefaultActorSpawner::new()),
+            || {
+                let overflow = crate::OverflowStrategy::Fail;
+                Box::new(DefaultMailbox::with_overflow(1, overflow))
+            },
+        );
+        let gate = system.spawn_props("gate", props).await.unwrap();
+
+        let permits = std::sync::Arc::new(tokio::sync::Semaphore::new(0));
+        let (entered, wait) = oneshot::channel();
+        gate.tell(Pass(permits.clone(), Some(entered)))
+            .await
+            .unwrap();
+        wait.await.unwrap();
+        gate.tell(Pass(permits.clone(), None)).await.unwrap();
+        assert!(gate.try_tell(Pass(permits.clone(), None)).is_err());
+
+        // system messages skip the capacity instead of failing
+        gate.untyped().poison().await.unwrap();
+        permits.add_permits(2);
+        let stopped = tokio::time::timeout(Duration::from_secs(5), system.stop_actor(gate.path()));
+        stopped.await.unwrap().unwrap();
+        assert!(gate.is_closed());
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{HashMap, HashSet};
+
+use tokio::{sync::watch, task::AbortHandle};
+
+use crate::{ActorError, ActorPath, Result, UntypedActorRef, context::Watchers};
+
+#[derive(Debug)]
+pub(crate) struct ActorEntry {
+    pub(crate) actor_ref: UntypedActorRef,
+    /// Name of the actor type, for events published without the actor.
+    pub(crate) actor_type: &'static str,
+    /// Closed once the actor task has finished.
+    pub(crate) terminated: watch::Receiver<()>,
+    pub(crate) abort_handle: AbortHandle,
+    pub(crate) max_children: Option<usize>,
+    pub(crate) children: HashSet<ActorPath>,
+    pub(crate) watchers: Watchers,
+}
+
+impl ActorEntry {
+    pub(crate) fn is_terminated(&self) -> bool {
+        self.terminated.has_changed().is_err()
+    }
+}
+
+pub(crate) type Level = Vec<(UntypedActorRef, watch::Receiver<()>)>;
+
+/// Running actors of a system. Every entry knows its children, so walking a
+/// subtree never has to look at unrelated actors.
+#[derive(Debug, Default)]
+pub(crate) struct Registry {
+    actors: HashMap<ActorPath, ActorEntry>,
+    top_level: HashSet<ActorPath>,
+}
+
+impl Registry {
+    pub(crate) fn get(&self, path: &ActorPath) -> Option<&ActorEntry> {
+        self.actors.get(path)
+    }
+
+    /// Checks whether an actor can be spawned at `path` and returns its parent.
+    pub(crate) fn check_spawn(&self, path: &ActorPath) -> Result<Option<UntypedActorRef>> {
+        if self.actors.contains_key(path) {
+            return Err(ActorError::Exists(path.clone()));
+        }
+
+        let Some(parent) = path.parent() else {
+            return Ok(None);
+        };
+        let Some(entry) = self.actors.get(&parent) else {
+            return Err(ActorError::ParentNotFound(parent));
+        };
+        if entry
+            .max_children
+            .is_some_and(|max| entry.children.len() >= max)
+        {
+            return Err(ActorError::TooManyChildren(parent));
+        }
+
+        Ok(Some(entry.actor_ref.clone()))
+    }
+
+    pub(crate) fn insert(&mut self, path: ActorPath, entry: ActorEntry) {
+        if let Some(parent) = path
+            .parent()
+            .and_then(|parent| self.actors.get_mut(&parent))
+        {
+            parent.children.insert(path.clone());
+        }
+        if !path.has_parent() {
+            self.top_level.insert(path.clone());
+        }
+        self.actors.insert(path, entry);
+    }
+
+    pub(crate) fn remove(&mut self, path: &ActorPath) -> Option<ActorEntry> {
+        let entry = self.actors.remove(path)?;
+        if let Some(parent) = path
+            .parent()
+            .and_then(|parent| self.actors.get_mut(&parent))
+        {
+            parent.children.remove(path);
+        }
+        self.top_level.remove(path);
+        Some(entry)
+    }
+
+    pub(crate) fn children(&self, path: &ActorPath) -> Vec<UntypedActorRef> {
+        let Some(entry) = self.actors.get(path) else {
+            return Vec::new();
+        };
+
+        entry
+            .children
+            .iter()
+            .filter_map(|child| self.actors.get(child))
+            .map(|child| child.actor_ref.clone())
+            .collect()
+    }
+
+    /// Actors below `root` whose names match `pattern` segment by segment,
+    /// `matches` deciding whether a name matches a segment. The literal
+    /// prefix of the pattern is looked up directly, only segments with
+    /// wildcards walk the children.
+    pub(crate) fn select(
+        &self,
+        root: &ActorPath,
+        pattern: &[String],
+        is_literal: impl Fn(&str) -> bool,
+        matches: impl Fn(&str, &str) -> bool,
+    ) -> Vec<UntypedActorRef> {
+        let literal = pattern.iter().take_while(|segment| is_literal(segment));
+        let mut prefix = root.clone();
+        for name in literal.clone() {
+            let Ok(child) = prefix.generated_child(name) else {
+                return Vec::new();
+            };
+            prefix = child;
+        }
+
+        let (mut paths, rest): (Vec<&ActorPath>, _) = match literal.count() {
+            0 => {
+                let Some((first, rest)) = pattern.split_first() else {
+                    return Vec::new();
+                };
+                let top_level = self.top_level.iter();
+                (
+                    top_level
+                        .filter(|path| matches(first, path.name()))
+                        .collect(),
+                    rest,
+                )
+            }
+            count => match self.actors.get_key_value(&prefix) {
+                Some((path, _)) => (vec![path], &pattern[count..]),
+                None => return Vec::new(),
+            },
+        };
+
+        for segment in rest {
+            paths = paths
+                .into_iter()
+                .filter_map(|path| self.actors.get(path))
+                .flat_map(|entry| &entry.children)
+                .filter(|child| matches(segment, child.name()))
+                .collect();
+        }
+
+        paths
+            .into_iter()
+            .filter_map(|path| self.actors.get(path))
+            .map(|entry| entry.actor_ref.clone())
+            .collect()
+    }
+
+    /// The actor at `path` and its descendants, one level per generation.
+    pub(crate) fn subtree(&self, path: &ActorPath) -> Vec<Level> {
+        let mut levels = Vec::new();
+        let mut paths = vec![path.clone()];
+        while !paths.is_empty() {
+            let mut level = Level::new();
+            let mut next = Vec::new();
+            for entry in paths.iter().filter_map(|path| self.actors.get(path)) {
+                level.push((entry.actor_ref.clone(), entry.terminated.clone()));
+                next.extend(entry.children.iter().cloned());
+            }
+            levels.push(level);
+            paths = next;
+        }
+        levels
+    }
+
+    /// All actors, one level per depth of their path, top level actors first.
+    pub(crate) fn top_level(&self) -> Vec<ActorPath> {
+        self.top_level.iter().cloned().collect()
+    }
+
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = (ActorPath, ActorEntry)> + '_ {
+        self.top_level.clear();
+        self.actors.drain()
+    }
+}
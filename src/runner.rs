@@ -0,0 +1,162 @@
+use std::{future::Future, sync::Arc};
+
+use tokio::sync::{oneshot, watch};
+
+use crate::{
+    Actor, ActorContext, ActorError, LifecycleEventKind, Mailbox, MessageHandlerResult, Result,
+    error::panic_message,
+    handler::catch_unwind,
+    supervisor::{Directive, RestartStats, SupervisorStrategy},
+    system::SystemMessage,
+};
+
+/// Drives a single actor from start to stop: processes its mailbox, lets the
+/// supervisor handle failures and notifies watchers once it has stopped.
+/// `ActorSpawner`s decide where `run` is executed.
+pub struct ActorRunner<A: Actor> {
+    ctx: ActorContext,
+    mailbox: Box<dyn Mailbox<A>>,
+    actor_fn: Box<dyn Fn() -> A + Send + Sync>,
+    restarts: RestartStats,
+    /// Dropped together with the runner, which tells waiters the actor is gone.
+    _terminated: watch::Sender<()>,
+}
+
+impl<A: Actor> ActorRunner<A> {
+    pub(crate) fn new(
+        ctx: ActorContext,
+        mailbox: Box<dyn Mailbox<A>>,
+        actor_fn: Box<dyn Fn() -> A + Send + Sync>,
+        terminated: watch::Sender<()>,
+    ) -> Self {
+        Self {
+            ctx,
+            mailbox,
+            actor_fn,
+            restarts: RestartStats::default(),
+            _terminated: terminated,
+        }
+    }
+
+    pub async fn run(mut self) {
+        let mut actor = (self.actor_fn)();
+        let mut next = failed(catch_hook(actor.started(&mut self.ctx)).await);
+        let mut started = next.is_none();
+        if started {
+            self.publish(LifecycleEventKind::Started);
+        }
+
+        let reason = loop {
+            let result = match next.take() {
+                Some(result) => result,
+                None => {
+                    self.mailbox
+                        .process_messages(&mut self.ctx, &mut actor)
+                        .await
+                }
+            };
+
+            match result {
+                MessageHandlerResult::Stop { reason } => break reason,
+                MessageHandlerResult::Restart => {
+                    self.ctx.system.stop_children(&self.ctx.path).await;
+                    self.ctx.timers.cancel_all();
+                    self.ctx.behaviors.clear();
+                    actor = (self.actor_fn)();
+                    next = failed(catch_hook(actor.restarting(&mut self.ctx, None)).await);
+                    started = next.is_none();
+                    if started {
+                        self.publish(LifecycleEventKind::Restarted);
+                    }
+                }
+                MessageHandlerResult::Failed(error) => {
+                    let error = Arc::new(error);
+                    match self.supervise(&error).await {
+                        Directive::Resume => {}
+                        Directive::Restart => {
+                            self.ctx.system.stop_children(&self.ctx.path).await;
+                            self.ctx.timers.cancel_all();
+                            self.ctx.behaviors.clear();
+                            actor = (self.actor_fn)();
+                            let restarting = actor.restarting(&mut self.ctx, Some(&error));
+                            next = failed(catch_hook(restarting).await);
+                            started = next.is_none();
+                            if started {
+                                self.publish(LifecycleEventKind::Restarted);
+                            }
+                        }
+                        Directive::Stop => break format!("failed: {error:?}"),
+                        Directive::Escalate => break format!("escalated: {error:?}"),
+                    }
+                }
+                MessageHandlerResult::Timeout
+                | MessageHandlerResult::None
+                | MessageHandlerResult::Stash => {}
+            }
+        };
+
+        self.publish(LifecycleEventKind::Stopping);
+        self.ctx.timers.cancel_all();
+        self.mailbox.drain(&mut self.ctx).await;
+        self.ctx
+            .system
+            .receptionist()
+            .remove_actor(&self.ctx.myself);
+
+        self.ctx.system.stop_children(&self.ctx.path).await;
+        self.ctx.system.remove_actor(&self.ctx.myself).await;
+
+        if started && let Err(payload) = catch_unwind(actor.stopped(&mut self.ctx)).await {
+            self.publish(LifecycleEventKind::HookPanicked {
+                hook: "stopped",
+                payload: panic_message(&*payload),
+            });
+        }
+
+        self.ctx.notify_watchers(&reason).await;
+        if let Some(parent) = &self.ctx.parent {
+            let terminated = SystemMessage::ChildTerminated(self.ctx.myself.clone());
+            let _ = parent.send_system(terminated).await;
+        }
+        self.publish(LifecycleEventKind::Stopped { reason });
+    }
+
+    fn publish(&self, kind: LifecycleEventKind) {
+        let uid = self.ctx.myself.uid();
+        self.ctx
+            .system
+            .lifecycle
+            .publish::<A>(&self.ctx.path, uid, kind);
+    }
+
+    /// Asks the parent what to do about `error`. Top level actors are
+    /// supervised with the default strategy.
+    async fn supervise(&mut self, error: &Arc<ActorError>) -> Directive {
+        let Some(parent) = self.ctx.parent.clone() else {
+            return SupervisorStrategy::default().decide(error, &mut self.restarts);
+        };
+
+        let (reply_to, reply) = oneshot::channel();
+        let msg = SystemMessage::Failed {
+            child: self.ctx.myself.clone(),
+            error: error.clone(),
+            reply_to,
+        };
+        if parent.send_system(msg).await.is_err() {
+            return Directive::Stop;
+        }
+
+        reply.await.unwrap_or(Directive::Stop)
+    }
+}
+
+fn failed(result: Result<()>) -> Option<MessageHandlerResult> {
+    result.err().map(MessageHandlerResult::Failed)
+}
+
+/// Runs a lifecycle hook, turning a panic into an error.
+async fn catch_hook(hook: impl Future<Output = Result<()>>) -> Result<()> {
+    catch_unwind(hook)
+        .await
+        .unwrap_or_else(|payload| Err(ActorError::panicked(None, payload)))
+}
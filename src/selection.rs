@@ -0,0 +1,332 @@
+use std::{fmt::Display, time::Duration};
+
+use tokio::{sync::broadcast::error::RecvError, time::Instant};
+
+use crate::{
+    Actor, ActorError, ActorPath, ActorRef, ActorSystem, Handler, LifecycleEventKind, Message,
+    Result, UntypedActorRef,
+};
+
+/// Actors whose path matches a pattern, see `ActorSystem::select` and
+/// `ActorContext::select`. Every path segment of the pattern can use `*` for
+/// any number of characters and `?` for a single one. The registry is looked
+/// up again every time the selection is used.
+#[derive(Clone, Debug)]
+pub struct ActorSelection {
+    system: ActorSystem,
+    /// `None` if the pattern leaves the root with `..`, nothing matches then.
+    segments: Option<Vec<String>>,
+}
+
+impl ActorSelection {
+    /// Selection of `pattern` relative to `base`, or absolute if it starts
+    /// with `/` or there is no `base`.
+    pub(crate) fn new(system: ActorSystem, base: Option<&ActorPath>, pattern: &str) -> Self {
+        let mut segments: Vec<String> = match base {
+            Some(base) if !pattern.starts_with('/') => base.segments().to_vec(),
+            _ => Vec::new(),
+        };
+
+        let mut valid = true;
+        for segment in pattern.split('/') {
+            match segment {
+                "" | "." => {}
+                ".." => valid &= segments.pop().is_some(),
+                segment => segments.push(segment.to_string()),
+            }
+        }
+
+        Self {
+            system,
+            segments: (valid && !segments.is_empty()).then_some(segments),
+        }
+    }
+
+    /// All actors currently matching the selection, ordered by path.
+    pub async fn resolve(&self) -> Vec<UntypedActorRef> {
+        let Some(segments) = &self.segments else {
+            return Vec::new();
+        };
+
+        let mut matches = self.system.select_actors(segments, is_literal, glob).await;
+        matches.sort_unstable_by(|a, b| a.path().cmp(b.path()));
+        matches
+    }
+
+    fn matches(&self, path: &ActorPath) -> bool {
+        let Some(segments) = &self.segments else {
+            return false;
+        };
+
+        path.segments().len() == segments.len()
+            && segments
+                .iter()
+                .zip(path.segments())
+                .all(|(segment, name)| glob(segment, name))
+    }
+
+    /// Sends `msg` to every matching actor of type `A`. Matching actors of
+    /// other types can't be sent `msg` and are skipped, the report counts
+    /// them separately.
+    pub async fn tell<A, M>(&self, msg: M) -> TellReport
+    where
+        A: Handler<M>,
+        M: Message + Clone,
+    {
+        let mut report = TellReport::default();
+        for actor_ref in self.resolve().await {
+            match actor_ref.downcast::<A>() {
+                Some(actor_ref) => {
+                    if actor_ref.tell(msg.clone()).await.is_ok() {
+                        report.sent += 1;
+                    }
+                }
+                None => report.mismatched += 1,
+            }
+        }
+        report
+    }
+
+    /// Returns the first matching actor of type `A`, waiting up to `timeout`
+    /// for one to be spawned. Matching actors of other types are skipped,
+    /// `ActorError::WrongActorType` is returned if only those were found.
+    pub async fn resolve_one<A: Actor>(&self, timeout: Duration) -> Result<ActorRef<A>> {
+        let deadline = Instant::now() + timeout;
+        // subscribed first, so no spawn between the lookup and the wait is missed
+        let mut events = self.system.subscribe_lifecycle();
+        loop {
+            let mut mismatched = None;
+            let found = self.resolve().await.iter().find_map(|actor_ref| {
+                let found = actor_ref.downcast::<A>();
+                if found.is_none() {
+                    mismatched.get_or_insert_with(|| actor_ref.path().clone());
+                }
+                found.filter(|actor_ref| !actor_ref.is_closed())
+            });
+            if let Some(actor_ref) = found {
+                return Ok(actor_ref);
+            }
+
+            // spawned actors are in the registry once `resolve` can read it
+            loop {
+                let event = tokio::time::timeout_at(deadline, events.recv()).await;
+                match event {
+                    Err(_) | Ok(Err(RecvError::Closed)) => {
+                        return Err(match mismatched {
+                            Some(path) => ActorError::WrongActorType(path),
+                            None => ActorError::NotFound(self.to_string()),
+                        });
+                    }
+                    Ok(Err(RecvError::Lagged(_))) => break,
+                    Ok(Ok(event))
+                        if event.kind == LifecycleEventKind::Spawned
+                            && self.matches(&event.path) =>
+                    {
+                        break;
+                    }
+                    Ok(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+/// Outcome of `ActorSelection::tell`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TellReport {
+    /// Matching actors the message was sent to.
+    pub sent: usize,
+    /// Matching actors of another type, which were skipped.
+    pub mismatched: usize,
+}
+
+impl Display for ActorSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.segments {
+            Some(segments) => write!(f, "{}", segments.join("/")),
+            None => write!(f, "<invalid>"),
+        }
+    }
+}
+
+/// Whether a pattern segment has no wildcards and only matches itself.
+fn is_literal(segment: &str) -> bool {
+    !segment.contains(['*', '?'])
+}
+
+/// Matches one path segment against a pattern segment.
+fn glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // position of the last `*` and the part of `name` it covers so far
+    let mut star = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use async_trait::async_trait;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn glob_matches_segments() {
+        assert!(glob("*", "worker-1"));
+        assert!(glob("worker-*", "worker-1"));
+        assert!(glob("w?rker-*1", "worker-11"));
+        assert!(!glob("worker-?", "worker-11"));
+        assert!(!glob("*-2", "worker-1"));
+    }
+
+    struct Worker {
+        jobs: mpsc::UnboundedSender<ActorPath>,
+    }
+
+    #[async_trait]
+    impl Actor for Worker {}
+
+    #[derive(Clone)]
+    struct Job;
+    impl Message for Job {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Job> for Worker {
+        async fn handle(&mut self, _msg: Job, ctx: &mut ActorContext) {
+            self.jobs.send(ctx.path.clone()).unwrap();
+        }
+    }
+
+    struct Sibling;
+    impl Message for Sibling {
+        type Response = Option<ActorPath>;
+    }
+
+    #[async_trait]
+    impl Handler<Sibling> for Worker {
+        async fn handle(&mut self, _msg: Sibling, ctx: &mut ActorContext) -> Option<ActorPath> {
+            let sibling = ctx.select("../worker-2");
+            let sibling = sibling.resolve_one::<Worker>(Duration::ZERO).await;
+            sibling.ok().map(|sibling| sibling.path().clone())
+        }
+    }
+
+    struct Spawn(&'static str);
+    impl Message for Spawn {
+        type Response = ActorRef<Worker>;
+    }
+
+    #[async_trait]
+    impl Handler<Spawn> for Worker {
+        async fn handle(&mut self, msg: Spawn, ctx: &mut ActorContext) -> ActorRef<Worker> {
+            let jobs = self.jobs.clone();
+            ctx.spawn(msg.0, move || Worker { jobs: jobs.clone() }, 10)
+                .await
+                .unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn select_broadcasts_and_resolves() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let workers = system
+            .spawn("workers", move || Worker { jobs: tx.clone() }, 10)
+            .await
+            .unwrap();
+        let first = workers.ask(Spawn("worker-1")).await.unwrap();
+        workers.ask(Spawn("worker-2")).await.unwrap();
+
+        let report = system.select("workers/*").tell::<Worker, _>(Job).await;
+        assert_eq!(report.sent, 2);
+        let mut paths = vec![rx.recv().await.unwrap(), rx.recv().await.unwrap()];
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "workers/worker-1".parse().unwrap(),
+                "workers/worker-2".parse().unwrap()
+            ]
+        );
+
+        assert_eq!(system.select("*/worker-?").resolve().await.len(), 2);
+        assert_eq!(system.select("w*").resolve().await.len(), 1);
+
+        let sibling = first.ask(Sibling).await.unwrap();
+        assert_eq!(sibling, "workers/worker-2".parse().ok());
+
+        let missing = system.select("workers/nobody");
+        let missing = missing.resolve_one::<Worker>(Duration::from_millis(20));
+        assert!(matches!(missing.await, Err(ActorError::NotFound(_))));
+        assert!(system.select("workers/../..").resolve().await.is_empty());
+    }
+
+    struct Idle;
+    #[async_trait]
+    impl Actor for Idle {}
+
+    #[tokio::test]
+    async fn other_actor_types_are_reported() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        system
+            .spawn("workers", move || Worker { jobs: tx.clone() }, 10)
+            .await
+            .unwrap();
+        let idle = system.spawn("watchman", || Idle, 10).await.unwrap();
+
+        let report = system.select("w*").tell::<Worker, _>(Job).await;
+        assert_eq!((report.sent, report.mismatched), (1, 1));
+        assert_eq!(rx.recv().await.unwrap(), "workers".parse().unwrap());
+
+        let watchman = system.select("watchman");
+        let watchman = watchman.resolve_one::<Worker>(Duration::ZERO).await;
+        assert!(matches!(watchman, Err(ActorError::WrongActorType(path)) if path == *idle.path()));
+    }
+
+    #[tokio::test]
+    async fn resolve_one_waits_for_spawn() {
+        let system = ActorSystem::new();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let workers = system
+            .spawn("workers", move || Worker { jobs: tx.clone() }, 10)
+            .await
+            .unwrap();
+
+        let selection = system.select("workers/late-*");
+        let late = tokio::spawn(async move {
+            selection
+                .resolve_one::<Worker>(Duration::from_secs(5))
+                .await
+        });
+        tokio::task::yield_now().await;
+        let spawned = workers.ask(Spawn("late-1")).await.unwrap();
+
+        assert_eq!(late.await.unwrap().unwrap().path(), spawned.path());
+    }
+}
@@ -1,9 +1,11 @@
-use std::marker::PhantomData;
+use std::{future::Future, marker::PhantomData, pin::Pin, sync::Arc};
 
-use crate::{Actor, ActorContext, ActorRef, Mailbox};
+use tokio::{runtime::Handle, task::JoinHandle};
+
+use crate::{Actor, ActorRunner};
 
 pub trait ActorSpawner<A: Actor> {
-    fn spawn(&self, ctx: ActorContext, actor: A, mailbox: Box<dyn Mailbox<A>>) -> ActorRef<A>;
+    fn spawn(&self, runner: ActorRunner<A>) -> JoinHandle<()>;
 }
 
 #[derive(Default)]
@@ -20,26 +22,54 @@ impl<A: Actor> DefaultActorSpawner<A> {
 }
 
 impl<A: Actor> ActorSpawner<A> for DefaultActorSpawner<A> {
-    fn spawn(
-        &self,
-        mut ctx: ActorContext,
-        mut actor: A,
-        mut mailbox: Box<dyn Mailbox<A>>,
-    ) -> ActorRef<A> {
-        let actor_ref = ActorRef::new(ctx.path.clone(), mailbox.take_sender());
+    fn spawn(&self, runner: ActorRunner<A>) -> JoinHandle<()> {
+        tokio::spawn(runner.run())
+    }
+}
+
+pub type ActorTask = Pin<Box<dyn Future<Output = ()> + Send>>;
 
-        tokio::spawn(async move {
-            if actor.started(&mut ctx).await.is_err() {
-                return;
-            }
+/// Spawns the tasks of actors of any type, see `ActorSystemBuilder::with_spawner`.
+pub trait TaskSpawner: Send + Sync + 'static {
+    fn spawn(&self, task: ActorTask) -> JoinHandle<()>;
+}
+
+/// Spawns actors on another runtime.
+impl TaskSpawner for Handle {
+    fn spawn(&self, task: ActorTask) -> JoinHandle<()> {
+        Handle::spawn(self, task)
+    }
+}
 
-            mailbox.process_messages(&mut ctx, &mut actor).await;
+/// The default spawner of a system, used by `ActorSystem::spawn`,
+/// `ActorContext::spawn` and `ActorSystem::props`.
+#[derive(Clone, Default)]
+pub(crate) struct SystemSpawner {
+    /// Spawns with `tokio::spawn` if not set.
+    spawner: Option<Arc<dyn TaskSpawner>>,
+}
 
-            ctx.system.stop_actor(&ctx.path).await;
+impl SystemSpawner {
+    pub(crate) fn new(spawner: impl TaskSpawner) -> Self {
+        Self {
+            spawner: Some(Arc::new(spawner)),
+        }
+    }
+}
 
-            actor.stopped(&mut ctx).await;
-        });
+impl<A: Actor> ActorSpawner<A> for SystemSpawner {
+    fn spawn(&self, runner: ActorRunner<A>) -> JoinHandle<()> {
+        match &self.spawner {
+            Some(spawner) => spawner.spawn(Box::pin(runner.run())),
+            None => tokio::spawn(runner.run()),
+        }
+    }
+}
 
-        actor_ref
+impl std::fmt::Debug for SystemSpawner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemSpawner")
+            .field("custom", &self.spawner.is_some())
+            .finish()
     }
 }
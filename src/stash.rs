@@ -0,0 +1,172 @@
+use std::{collections::VecDeque, fmt::Debug};
+
+use crate::{Actor, ActorContext, BoxedMessageHandler};
+
+/// Messages set aside with `ActorContext::stash`, kept by the mailbox.
+pub(crate) struct Stash<A: Actor> {
+    stashed: VecDeque<BoxedMessageHandler<A>>,
+    unstashed: VecDeque<BoxedMessageHandler<A>>,
+}
+
+impl<A: Actor> Stash<A> {
+    pub(crate) fn push(&mut self, msg: BoxedMessageHandler<A>, ctx: &mut ActorContext) {
+        self.stashed.push_back(msg);
+        ctx.stash_size = self.stashed.len();
+    }
+
+    /// Returns the next unstashed message, which is processed before anything
+    /// still waiting in the mailbox.
+    pub(crate) fn pop(&mut self, ctx: &mut ActorContext) -> Option<BoxedMessageHandler<A>> {
+        let count = ctx.unstash_requested.min(self.stashed.len());
+        ctx.unstash_requested = 0;
+        self.unstashed.extend(self.stashed.drain(..count));
+        ctx.stash_size = self.stashed.len();
+
+        self.unstashed.pop_front()
+    }
+
+    pub(crate) fn drain(&mut self, ctx: &mut ActorContext) -> Vec<BoxedMessageHandler<A>> {
+        ctx.stash_size = 0;
+        self.unstashed
+            .drain(..)
+            .chain(self.stashed.drain(..))
+            .collect()
+    }
+}
+
+impl<A: Actor> Default for Stash<A> {
+    fn default() -> Self {
+        Self {
+            stashed: VecDeque::new(),
+            unstashed: VecDeque::new(),
+        }
+    }
+}
+
+impl<A: Actor> Debug for Stash<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Stash")
+            .field("stashed", &self.stashed.len())
+            .field("unstashed", &self.unstashed.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ActorError, prelude::*};
+    use async_trait::async_trait;
+
+    #[derive(Default)]
+    struct Gate {
+        open: bool,
+        seen: Vec<u32>,
+    }
+
+    #[async_trait]
+    impl Actor for Gate {}
+
+    struct Open;
+    impl Message for Open {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Open> for Gate {
+        async fn handle(&mut self, _msg: Open, ctx: &mut ActorContext) {
+            self.open = true;
+            ctx.unstash_all();
+        }
+    }
+
+    struct Item(u32);
+    impl Message for Item {
+        type Response = Vec<u32>;
+    }
+
+    #[async_trait]
+    impl Handler<Item> for Gate {
+        async fn handle(&mut self, msg: Item, ctx: &mut ActorContext) -> Vec<u32> {
+            if !self.open {
+                ctx.stash(msg).unwrap();
+                return Vec::new();
+            }
+            self.seen.push(msg.0);
+            self.seen.clone()
+        }
+    }
+
+    struct Stashed;
+    impl Message for Stashed {
+        type Response = usize;
+    }
+
+    #[async_trait]
+    impl Handler<Stashed> for Gate {
+        async fn handle(&mut self, _msg: Stashed, ctx: &mut ActorContext) -> usize {
+            ctx.stash_size()
+        }
+    }
+
+    /// Replies whether stashing it overflowed the stash.
+    struct Overflow;
+    impl Message for Overflow {
+        type Response = bool;
+    }
+
+    #[async_trait]
+    impl Handler<Overflow> for Gate {
+        async fn handle(&mut self, msg: Overflow, ctx: &mut ActorContext) -> bool {
+            matches!(ctx.stash(msg), Err(ActorError::StashOverflow(_)))
+        }
+    }
+
+    /// Replies whether stashing another message was refused.
+    struct Smuggle;
+    impl Message for Smuggle {
+        type Response = bool;
+    }
+
+    #[async_trait]
+    impl Handler<Smuggle> for Gate {
+        async fn handle(&mut self, _msg: Smuggle, ctx: &mut ActorContext) -> bool {
+            matches!(ctx.stash(Item(0)), Err(ActorError::NotStashable(_)))
+        }
+    }
+
+    #[tokio::test]
+    async fn stashed_messages_are_replayed_in_order() {
+        let system = ActorSystem::new();
+        let gate = system.spawn("gate", Gate::default, 10).await.unwrap();
+
+        let first = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.ask(Item(1)).await }
+        });
+        while gate.ask(Stashed).await.unwrap() == 0 {
+            tokio::task::yield_now().await;
+        }
+        gate.tell(Item(2)).await.unwrap();
+        gate.tell(Open).await.unwrap();
+
+        assert_eq!(first.await.unwrap().unwrap(), vec![1]);
+        assert_eq!(gate.ask(Item(3)).await.unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn stash_is_bounded() {
+        let system = ActorSystem::new();
+        let props = ActorProps::new(
+            Gate::default,
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(DefaultMailbox::new(10)),
+        )
+        .with_stash_capacity(1);
+        let gate = system.spawn_props("gate", props).await.unwrap();
+
+        assert!(gate.ask(Smuggle).await.unwrap());
+        gate.tell(Overflow).await.unwrap();
+        assert!(gate.ask(Overflow).await.unwrap());
+        assert_eq!(gate.ask(Stashed).await.unwrap(), 1);
+    }
+}
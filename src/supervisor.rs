@@ -0,0 +1,306 @@
+use std::{collections::VecDeque, fmt::Debug, sync::Arc, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::ActorError;
+
+/// What a supervisor does with a child that failed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Directive {
+    /// Keep the current state and continue with the next message.
+    Resume,
+    /// Recreate the actor from its props and call `Actor::restarting`.
+    Restart,
+    /// Stop the actor.
+    Stop,
+    /// Stop the actor and fail the supervisor itself with the child's error.
+    Escalate,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Scope {
+    OneForOne,
+    AllForOne,
+}
+
+pub type Decider = Arc<dyn Fn(&ActorError) -> Directive + Send + Sync>;
+
+/// Decides what happens to the children of an actor when one of them fails.
+///
+/// With `one_for_one` only the failed child is affected, with `all_for_one` a
+/// restart or stop is applied to all of its siblings as well. A child that is
+/// restarted more than `max_restarts` times within `within` is stopped instead.
+#[derive(Clone)]
+pub struct SupervisorStrategy {
+    scope: Scope,
+    max_restarts: usize,
+    within: Duration,
+    decider: Decider,
+}
+
+impl SupervisorStrategy {
+    pub fn one_for_one(max_restarts: usize, within: Duration) -> Self {
+        Self::new(Scope::OneForOne, max_restarts, within)
+    }
+
+    pub fn all_for_one(max_restarts: usize, within: Duration) -> Self {
+        Self::new(Scope::AllForOne, max_restarts, within)
+    }
+
+    fn new(scope: Scope, max_restarts: usize, within: Duration) -> Self {
+        Self {
+            scope,
+            max_restarts,
+            within,
+            decider: Arc::new(|_| Directive::Restart),
+        }
+    }
+
+    pub fn with_decider<D>(mut self, decider: D) -> Self
+    where
+        D: Fn(&ActorError) -> Directive + Send + Sync + 'static,
+    {
+        self.decider = Arc::new(decider);
+        self
+    }
+
+    pub fn is_all_for_one(&self) -> bool {
+        self.scope == Scope::AllForOne
+    }
+
+    pub(crate) fn decide(&self, error: &ActorError, stats: &mut RestartStats) -> Directive {
+        match (self.decider)(error) {
+            Directive::Restart if !stats.record(self.max_restarts, self.within) => Directive::Stop,
+            directive => directive,
+        }
+    }
+}
+
+impl Default for SupervisorStrategy {
+    fn default() -> Self {
+        Self::one_for_one(10, Duration::from_secs(60))
+    }
+}
+
+impl Debug for SupervisorStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SupervisorStrategy")
+            .field("scope", &self.scope)
+            .field("max_restarts", &self.max_restarts)
+            .field("within", &self.within)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Default, Debug)]
+pub(crate) struct RestartStats {
+    restarts: VecDeque<Instant>,
+}
+
+impl RestartStats {
+    /// Records a restart, returns false if it exceeds the allowed number of restarts.
+    fn record(&mut self, max_restarts: usize, within: Duration) -> bool {
+        let now = Instant::now();
+        while let Some(first) = self.restarts.front() {
+            if now.duration_since(*first) > within {
+                self.restarts.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if self.restarts.len() >= max_restarts {
+            return false;
+        }
+
+        self.restarts.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LifecycleEventKind;
+    use crate::prelude::*;
+    use async_trait::async_trait;
+
+    struct Parent;
+    #[async_trait]
+    impl Actor for Parent {}
+
+    struct SpawnChild;
+    impl Message for SpawnChild {
+        type Response = ActorRef<Child>;
+    }
+
+    #[async_trait]
+    impl Handler<SpawnChild> for Parent {
+        async fn handle(&mut self, _msg: SpawnChild, ctx: &mut ActorContext) -> ActorRef<Child> {
+            ctx.spawn("child", || Child { counter: 0 }, 10)
+                .await
+                .unwrap()
+        }
+    }
+
+    struct Child {
+        counter: usize,
+    }
+    #[async_trait]
+    impl Actor for Child {}
+
+    struct Increment;
+    impl Message for Increment {
+        type Response = usize;
+    }
+
+    #[async_trait]
+    impl Handler<Increment> for Child {
+        async fn handle(&mut self, _msg: Increment, _ctx: &mut ActorContext) -> usize {
+            self.counter += 1;
+            self.counter
+        }
+    }
+
+    struct Panic;
+    impl Message for Panic {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Panic> for Child {
+        async fn handle(&mut self, _msg: Panic, _ctx: &mut ActorContext) {
+            panic!("boom");
+        }
+    }
+
+    struct Nursery;
+    #[async_trait]
+    impl Actor for Nursery {
+        async fn started(&mut self, ctx: &mut ActorContext) -> Result<()> {
+            ctx.spawn("child", || Child { counter: 0 }, 10).await?;
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl Handler<Panic> for Nursery {
+        async fn handle(&mut self, _msg: Panic, _ctx: &mut ActorContext) {
+            panic!("boom");
+        }
+    }
+
+    struct Supervised;
+    impl Message for Supervised {
+        type Response = usize;
+    }
+
+    #[async_trait]
+    impl Handler<Supervised> for Parent {
+        async fn handle(&mut self, _msg: Supervised, ctx: &mut ActorContext) -> usize {
+            ctx.restart_stats.len()
+        }
+    }
+
+    async fn spawn_child(strategy: SupervisorStrategy) -> ActorRef<Child> {
+        let system = ActorSystem::new();
+        let props = ActorProps::new(
+            || Parent,
+            || Box::new(DefaultActorSpawner::new()),
+            || Box::new(DefaultMailbox::new(10)),
+        )
+        .with_supervisor_strategy(strategy);
+
+        let parent = system.spawn_props("parent", props).await.unwrap();
+        parent.ask(SpawnChild).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn restart_recreates_state() {
+        let child = spawn_child(SupervisorStrategy::default()).await;
+
+        assert_eq!(child.ask(Increment).await.unwrap(), 1);
+        assert!(child.ask(Panic).await.is_err());
+        assert_eq!(child.ask(Increment).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn restart_replaces_children() {
+        let system = ActorSystem::new();
+        let mut events = system.subscribe_lifecycle();
+        let nursery = system.spawn("nursery", || Nursery, 10).await.unwrap();
+        let path = "nursery/child".parse().unwrap();
+        while events.recv().await.unwrap().path != path {}
+        let child = system.get::<Child>(&path).await.unwrap();
+
+        assert!(nursery.ask(Panic).await.is_err());
+        loop {
+            let event = events.recv().await.unwrap();
+            assert!(
+                !matches!(event.kind, LifecycleEventKind::Stopped { .. }) || event.path == path
+            );
+            if event.path == *nursery.path() && event.kind == LifecycleEventKind::Restarted {
+                break;
+            }
+        }
+
+        assert!(child.is_closed());
+        let child = system.get::<Child>(&path).await.unwrap();
+        assert_eq!(child.ask(Increment).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn resume_keeps_state() {
+        let strategy = SupervisorStrategy::default().with_decider(|_| Directive::Resume);
+        let child = spawn_child(strategy).await;
+
+        assert_eq!(child.ask(Increment).await.unwrap(), 1);
+        assert!(child.ask(Panic).await.is_err());
+        assert_eq!(child.ask(Increment).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn too_many_restarts_stop() {
+        let strategy = SupervisorStrategy::one_for_one(1, Duration::from_secs(60));
+        let child = spawn_child(strategy).await;
+
+        assert!(child.ask(Panic).await.is_err());
+        assert!(child.ask(Panic).await.is_err());
+        assert!(child.ask(Increment).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restarts_outside_the_window_are_forgotten() {
+        let strategy = SupervisorStrategy::one_for_one(1, Duration::from_secs(60));
+        let child = spawn_child(strategy).await;
+
+        assert!(child.ask(Panic).await.is_err());
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert!(child.ask(Panic).await.is_err());
+        assert_eq!(child.ask(Increment).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn restart_stats_are_dropped_with_the_child() {
+        let system = ActorSystem::new();
+        let parent = system.spawn("parent", || Parent, 10).await.unwrap();
+        let child = parent.ask(SpawnChild).await.unwrap();
+        let mut events = system.subscribe_lifecycle();
+
+        assert!(child.ask(Panic).await.is_err());
+        // the restart is done once the child handles messages again
+        child.ask(Increment).await.unwrap();
+        assert_eq!(parent.ask(Supervised).await.unwrap(), 1);
+
+        child.poison().await.unwrap();
+        loop {
+            let event = events.recv().await.unwrap();
+            if event.path == *child.path()
+                && matches!(event.kind, LifecycleEventKind::Stopped { .. })
+            {
+                break;
+            }
+        }
+        assert_eq!(parent.ask(Supervised).await.unwrap(), 0);
+    }
+}
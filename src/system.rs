@@ -1,16 +1,57 @@
 use async_trait::async_trait;
-use std::{any::Any, collections::HashMap, sync::Arc};
-use tokio::sync::RwLock;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{RwLock, broadcast, oneshot, watch},
+    task::JoinSet,
+    time::Instant,
+};
 
 use crate::{
-    Actor, ActorContext, ActorError, ActorPath, ActorProps, ActorRef, DefaultActorSpawner,
-    DefaultMailbox, Mailbox, Message, MessageHandlerResult, Result, handler::SystemHandler,
-    spawner::ActorSpawner,
+    Actor, ActorContext, ActorError, ActorPath, ActorProps, ActorRef, Address, DefaultMailbox,
+    Directive, Mailbox, Message, MessageHandlerResult, Result, UntypedActorRef,
+    dead_letters::{DeadLetter, DeadLetterCounts, DeadLetters},
+    handler::SystemHandler,
+    interceptor::{Interceptor, Interceptors},
+    lifecycle::{Lifecycle, LifecycleEvent, LifecycleEventKind},
+    receptionist::Receptionist,
+    registry::Registry,
+    selection::ActorSelection,
+    spawner::{ActorSpawner, SystemSpawner, TaskSpawner},
 };
 
+/// Outcome of `ActorSystem::shutdown`.
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownReport {
+    /// Actors that did not stop before the deadline and were aborted.
+    pub killed: Vec<ActorPath>,
+}
+
+/// Mailbox size of actors spawned with `ActorSystem::props`, unless the
+/// system is built with another one.
+const DEFAULT_MAILBOX_SIZE: usize = 100;
+
 #[derive(Clone, Debug)]
 pub struct ActorSystem {
-    actors: Arc<RwLock<HashMap<ActorPath, Box<dyn Any + Send + Sync>>>>,
+    address: Address,
+    actors: Arc<RwLock<Registry>>,
+    default_ask_timeout: Option<Duration>,
+    default_mailbox_size: usize,
+    pub(crate) spawner: SystemSpawner,
+    /// Id of the next generated actor name.
+    next_name_id: Arc<AtomicU64>,
+    shutting_down: Arc<AtomicBool>,
+    terminated: Arc<watch::Sender<Option<ShutdownReport>>>,
+    pub(crate) dead_letters: DeadLetters,
+    pub(crate) lifecycle: Lifecycle,
+    receptionist: Receptionist,
+    /// Interceptors every actor starts its chain with.
+    pub(crate) interceptors: Interceptors,
 }
 
 impl Default for ActorSystem {
@@ -20,26 +61,168 @@ impl Default for ActorSystem {
 }
 
 impl ActorSystem {
+    /// Local system named `default`, see `builder` for named systems.
     pub fn new() -> Self {
-        let actors = Arc::new(RwLock::new(HashMap::new()));
-        ActorSystem { actors }
+        Self::with_address(Address::default())
+    }
+
+    /// Builds a local system called `name`. Systems share nothing, not even
+    /// when they have the same name.
+    pub fn builder(name: &str) -> ActorSystemBuilder {
+        ActorSystemBuilder::new(name)
+    }
+
+    fn with_address(address: Address) -> Self {
+        let actors = Arc::new(RwLock::new(Registry::default()));
+        ActorSystem {
+            address,
+            actors,
+            default_ask_timeout: None,
+            default_mailbox_size: DEFAULT_MAILBOX_SIZE,
+            spawner: SystemSpawner::default(),
+            next_name_id: Arc::new(AtomicU64::new(0)),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            terminated: Arc::new(watch::channel(None).0),
+            dead_letters: DeadLetters::new(),
+            lifecycle: Lifecycle::new(),
+            receptionist: Receptionist::default(),
+            interceptors: Interceptors::default(),
+        }
     }
 
-    pub async fn spawn<A: Actor, F: Fn() -> A>(
+    /// Sets the ask timeout of every `ActorRef` spawned in this system. There
+    /// is none by default, asks wait for the response as long as it takes.
+    /// Clones of the system made before keep the old setting.
+    #[deprecated(note = "use `ActorSystemBuilder::with_default_ask_timeout`")]
+    pub fn with_default_ask_timeout(mut self, timeout: Duration) -> Self {
+        self.default_ask_timeout = Some(timeout);
+        self
+    }
+
+    /// Adds `interceptor` to the chain of every actor spawned in this system.
+    /// Clones of the system made before don't get it.
+    #[deprecated(note = "use `ActorSystemBuilder::with_interceptor`")]
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor) -> Self {
+        self.interceptors = self.interceptors.with(interceptor);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        self.address.system()
+    }
+
+    /// Address all actor paths of this system start with.
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn default_mailbox_size(&self) -> usize {
+        self.default_mailbox_size
+    }
+
+    /// Props of `actor_fn` using the default spawner of the system and a
+    /// `DefaultMailbox` of the default mailbox size.
+    #[allow(clippy::type_complexity)]
+    pub fn props<A, F>(
+        &self,
+        actor_fn: F,
+    ) -> ActorProps<
+        A,
+        F,
+        impl Fn() -> Box<dyn ActorSpawner<A>> + use<A, F>,
+        impl Fn() -> Box<dyn Mailbox<A>> + use<A, F>,
+    >
+    where
+        A: Actor,
+        F: Fn() -> A + Send + Sync + 'static,
+    {
+        self.buffered_props(actor_fn, self.default_mailbox_size)
+    }
+
+    /// Like `props`, but with a mailbox of size `buffer`.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn buffered_props<A, F>(
         &self,
-        name: &str,
         actor_fn: F,
         buffer: usize,
-    ) -> Result<ActorRef<A>> {
-        let props = ActorProps::new(
+    ) -> ActorProps<
+        A,
+        F,
+        impl Fn() -> Box<dyn ActorSpawner<A>> + use<A, F>,
+        impl Fn() -> Box<dyn Mailbox<A>> + use<A, F>,
+    >
+    where
+        A: Actor,
+        F: Fn() -> A + Send + Sync + 'static,
+    {
+        let spawner = self.spawner.clone();
+        ActorProps::new(
             actor_fn,
-            || Box::new(DefaultActorSpawner::new()),
-            || Box::new(DefaultMailbox::<A>::new(buffer)),
-        );
+            move || Box::new(spawner.clone()) as Box<dyn ActorSpawner<A>>,
+            move || Box::new(DefaultMailbox::<A>::new(buffer)) as Box<dyn Mailbox<A>>,
+        )
+    }
+
+    /// Ask timeout of the actors spawned in this system, `None` if asks are
+    /// unbounded, which is the default.
+    pub fn default_ask_timeout(&self) -> Option<Duration> {
+        self.default_ask_timeout
+    }
 
+    /// Subscribes to messages that could not be delivered from now on.
+    pub fn dead_letters(&self) -> broadcast::Receiver<DeadLetter> {
+        self.dead_letters.subscribe()
+    }
+
+    pub fn dead_letter_counts(&self) -> DeadLetterCounts {
+        self.dead_letters.counts()
+    }
+
+    /// Finds actors by the services they registered for.
+    pub fn receptionist(&self) -> &Receptionist {
+        &self.receptionist
+    }
+
+    /// Subscribes to lifecycle events of all actors from now on.
+    pub fn subscribe_lifecycle(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.lifecycle.subscribe()
+    }
+
+    pub async fn spawn<A: Actor, F: Fn() -> A + Send + Sync + 'static>(
+        &self,
+        name: &str,
+        actor_fn: F,
+        buffer: usize,
+    ) -> Result<ActorRef<A>> {
+        let props = self.buffered_props(actor_fn, buffer);
         self.spawn_props(name, props).await
     }
 
+    /// Spawns a top level actor with a generated name like `$a1`, see
+    /// `spawn_with_prefix` for more telling names.
+    pub async fn spawn_anonymous<A: Actor, F: Fn() -> A + Send + Sync + 'static>(
+        &self,
+        actor_fn: F,
+        buffer: usize,
+    ) -> Result<ActorRef<A>> {
+        let props = self.buffered_props(actor_fn, buffer);
+        let root = ActorPath::root(self.address.clone());
+        self.spawn_generated(&root, None, props).await
+    }
+
+    /// Spawns a top level actor with a generated name starting with `prefix`,
+    /// like `session-a1`.
+    pub async fn spawn_with_prefix<A: Actor, F: Fn() -> A + Send + Sync + 'static>(
+        &self,
+        prefix: &str,
+        actor_fn: F,
+        buffer: usize,
+    ) -> Result<ActorRef<A>> {
+        let props = self.buffered_props(actor_fn, buffer);
+        let root = ActorPath::root(self.address.clone());
+        self.spawn_generated(&root, Some(prefix), props).await
+    }
+
     pub async fn spawn_props<A, F, S, M>(
         &self,
         name: &str,
@@ -47,40 +230,81 @@ impl ActorSystem {
     ) -> Result<ActorRef<A>>
     where
         A: Actor,
-        F: Fn() -> A,
+        F: Fn() -> A + Send + Sync + 'static,
         S: Fn() -> Box<dyn ActorSpawner<A>>,
         M: Fn() -> Box<dyn Mailbox<A>>,
     {
-        let path = ActorPath::new(name);
-        if path.has_parent() {
-            return Err(ActorError::CreateError("invalid actor name".into()));
-        }
-
+        let path = ActorPath::root(self.address.clone()).child(name)?;
         self.spawn_path(path, props).await
     }
 
     pub(crate) async fn spawn_path<A, F, S, M>(
         &self,
         path: ActorPath,
-        mut props: ActorProps<A, F, S, M>,
+        props: ActorProps<A, F, S, M>,
+    ) -> Result<ActorRef<A>>
+    where
+        A: Actor,
+        F: Fn() -> A + Send + Sync + 'static,
+        S: Fn() -> Box<dyn ActorSpawner<A>>,
+        M: Fn() -> Box<dyn Mailbox<A>>,
+    {
+        self.spawn_at(|_| Ok(path), props).await
+    }
+
+    /// Spawns a child of `parent` with a name no other actor has, `$` or
+    /// `prefix-` followed by an id unique within the system.
+    pub(crate) async fn spawn_generated<A, F, S, M>(
+        &self,
+        parent: &ActorPath,
+        prefix: Option<&str>,
+        props: ActorProps<A, F, S, M>,
     ) -> Result<ActorRef<A>>
     where
         A: Actor,
-        F: Fn() -> A,
+        F: Fn() -> A + Send + Sync + 'static,
+        S: Fn() -> Box<dyn ActorSpawner<A>>,
+        M: Fn() -> Box<dyn Mailbox<A>>,
+    {
+        let generate = |actors: &Registry| loop {
+            let id = base36(self.next_name_id.fetch_add(1, Ordering::Relaxed));
+            let path = match prefix {
+                Some(prefix) => parent.child(&format!("{prefix}-{id}"))?,
+                None => parent.generated_child(&format!("${id}"))?,
+            };
+            // a prefixed name may have been taken by hand
+            if actors.get(&path).is_none() {
+                return Ok(path);
+            }
+        };
+        self.spawn_at(generate, props).await
+    }
+
+    /// Spawns at the path returned by `path`, which sees the registry as it
+    /// is while the actor is inserted.
+    async fn spawn_at<A, F, S, M>(
+        &self,
+        path: impl FnOnce(&Registry) -> Result<ActorPath>,
+        props: ActorProps<A, F, S, M>,
+    ) -> Result<ActorRef<A>>
+    where
+        A: Actor,
+        F: Fn() -> A + Send + Sync + 'static,
         S: Fn() -> Box<dyn ActorSpawner<A>>,
         M: Fn() -> Box<dyn Mailbox<A>>,
     {
         let mut actors = self.actors.write().await;
-        if actors.contains_key(&path) {
-            return Err(ActorError::Exists(path));
+        if self.shutting_down.load(Ordering::Acquire) {
+            return Err(ActorError::CreateError(
+                "actor system is shutting down".into(),
+            ));
         }
-
-        let actor_ref = props.spawn(self.clone(), path);
+        let path = path(&actors)?;
+        let parent = actors.check_spawn(&path)?;
+        let (actor_ref, entry) = props.spawn(self.clone(), path, parent);
 
         let path = actor_ref.path().clone();
-        let any = Box::new(actor_ref.clone());
-
-        actors.insert(path, any);
+        actors.insert(path, entry);
 
         Ok(actor_ref)
     }
@@ -89,41 +313,282 @@ impl ActorSystem {
         let actors = self.actors.read().await;
         actors
             .get(path)
-            .and_then(|any| any.downcast_ref::<ActorRef<A>>().cloned())
+            .and_then(|entry| entry.actor_ref.downcast::<A>())
     }
 
-    pub async fn stop_actor(&self, path: &ActorPath) {
-        let mut base_path = path.to_string();
-        base_path.push('/');
+    /// Selects the actors matching `pattern`, see `ActorSelection`.
+    pub fn select(&self, pattern: &str) -> ActorSelection {
+        ActorSelection::new(self.clone(), None, pattern)
+    }
 
-        let mut paths = Vec::new();
-        paths.push(path.clone());
+    /// Actors whose names match `pattern` segment by segment, see
+    /// `Registry::select`.
+    pub(crate) async fn select_actors(
+        &self,
+        pattern: &[String],
+        is_literal: impl Fn(&str) -> bool,
+        matches: impl Fn(&str, &str) -> bool,
+    ) -> Vec<UntypedActorRef> {
+        let root = ActorPath::root(self.address.clone());
+        let actors = self.actors.read().await;
+        actors.select(&root, pattern, is_literal, matches)
+    }
 
-        let running_actors = self.actors.read().await;
-        for running in running_actors.keys() {
-            if running.starts_with(&base_path) {
-                paths.push(running.clone());
+    pub(crate) async fn children(&self, path: &ActorPath) -> Vec<UntypedActorRef> {
+        self.actors.read().await.children(path)
+    }
+
+    /// Stops the actor at `path` and all of its descendants, children before
+    /// their parents, and waits until they are gone. An actor that cancels the
+    /// stop in `Actor::stopping` keeps running together with its ancestors and
+    /// `ActorError::StopCancelled` is returned.
+    ///
+    /// Called from a handler of the actor at `path` or one of its descendants
+    /// this never returns, as the stop waits for that handler. Use
+    /// `ActorContext::stop_self` or `ActorContext::stop` there instead.
+    pub async fn stop_actor(&self, path: &ActorPath) -> Result<()> {
+        self.stop_subtree(path, false).await
+    }
+
+    /// Stops the subtree at `path`, `forced` stops can't be cancelled.
+    async fn stop_subtree(&self, path: &ActorPath, forced: bool) -> Result<()> {
+        let levels = self.actors.read().await.subtree(path);
+        for level in levels.into_iter().rev() {
+            let mut stopping = JoinSet::new();
+            for (actor_ref, terminated) in level {
+                stopping.spawn(stop(actor_ref, terminated, forced));
             }
+
+            let mut cancelled = None;
+            while let Some(result) = stopping.join_next().await {
+                if let Ok(Some(path)) = result {
+                    cancelled.get_or_insert(path);
+                }
+            }
+            if let Some(path) = cancelled {
+                return Err(ActorError::StopCancelled(path));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops the children of a stopping actor. They can't cancel the stop,
+    /// they would be left without a parent otherwise.
+    pub(crate) async fn stop_children(&self, path: &ActorPath) {
+        let mut stopping = JoinSet::new();
+        for child in self.children(path).await {
+            let system = self.clone();
+            stopping.spawn(async move {
+                let _ = system.stop_subtree(child.path(), true).await;
+            });
         }
-        drop(running_actors);
+        stopping.join_all().await;
+    }
 
-        paths.sort_unstable();
-        paths.reverse();
+    /// Removes `actor` from the registry, unless it was already replaced by
+    /// a newer incarnation.
+    pub(crate) async fn remove_actor(&self, actor: &UntypedActorRef) {
+        let path = actor.path();
+        println!("removing {path}");
         let mut actors = self.actors.write().await;
-        for path in &paths {
-            println!("removing {path}");
+        if actors
+            .get(path)
+            .is_some_and(|entry| entry.actor_ref == *actor)
+        {
             actors.remove(path);
         }
     }
+
+    /// Stops all actors, children before their parents, and waits for them
+    /// to finish. The stops can't be cancelled, actors still running after
+    /// `timeout` are aborted and their watchers get `Terminated` with the
+    /// reason "killed".
+    pub async fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        if self.shutting_down.swap(true, Ordering::AcqRel) {
+            return self.when_terminated().await;
+        }
+
+        let deadline = Instant::now() + timeout;
+
+        // no new actors can be spawned anymore, so this is the final set,
+        // unrelated subtrees are stopped independently of each other
+        let mut stopping = JoinSet::new();
+        for path in self.actors.read().await.top_level() {
+            let system = self.clone();
+            stopping.spawn(async move {
+                let _ = system.stop_subtree(&path, true).await;
+            });
+        }
+        let _ = tokio::time::timeout_at(deadline, stopping.join_all()).await;
+
+        let mut report = ShutdownReport::default();
+        let mut killed = Vec::new();
+        for (path, entry) in self.actors.write().await.drain() {
+            if !entry.is_terminated() {
+                entry.abort_handle.abort();
+                killed.push((path, entry));
+            }
+        }
+
+        for (path, entry) in killed {
+            self.receptionist.remove_actor(&entry.actor_ref);
+            let reason = "killed".to_string();
+            let uid = entry.actor_ref.uid();
+            let stopped = LifecycleEventKind::Stopped {
+                reason: reason.clone(),
+            };
+            self.lifecycle
+                .publish_as(&path, uid, entry.actor_type, stopped);
+            let terminated = Terminated {
+                path: path.clone(),
+                uid,
+                reason,
+            };
+            entry.watchers.notify(terminated).await;
+            report.killed.push(path);
+        }
+        report.killed.sort_unstable();
+
+        self.terminated.send_replace(Some(report.clone()));
+        report
+    }
+
+    /// Resolves once `shutdown` has finished.
+    pub async fn when_terminated(&self) -> ShutdownReport {
+        let mut terminated = self.terminated.subscribe();
+        let report = terminated
+            .wait_for(Option::is_some)
+            .await
+            .expect("the sender lives as long as the system");
+        report.clone().unwrap_or_default()
+    }
+}
+
+/// Lowercase base 36 form of `id`, keeps generated names short.
+fn base36(mut id: u64) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[(id % 36) as usize]);
+        id /= 36;
+        if id == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Settings of a named system, see `ActorSystem::builder`.
+#[derive(Debug)]
+pub struct ActorSystemBuilder {
+    name: String,
+    default_ask_timeout: Option<Duration>,
+    default_mailbox_size: usize,
+    spawner: SystemSpawner,
+    interceptors: Interceptors,
+}
+
+impl ActorSystemBuilder {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            default_ask_timeout: None,
+            default_mailbox_size: DEFAULT_MAILBOX_SIZE,
+            spawner: SystemSpawner::default(),
+            interceptors: Interceptors::default(),
+        }
+    }
+
+    /// Sets the ask timeout of every `ActorRef` spawned in the system, asks
+    /// are unbounded otherwise.
+    pub fn with_default_ask_timeout(mut self, timeout: Duration) -> Self {
+        self.default_ask_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the mailbox size of actors spawned with `ActorSystem::props`.
+    pub fn with_default_mailbox_size(mut self, size: usize) -> Self {
+        self.default_mailbox_size = size;
+        self
+    }
+
+    /// Spawns actors with `spawner` unless their props bring their own,
+    /// instead of `tokio::spawn`.
+    pub fn with_spawner(mut self, spawner: impl TaskSpawner) -> Self {
+        self.spawner = SystemSpawner::new(spawner);
+        self
+    }
+
+    /// Adds `interceptor` to the chain of every actor spawned in the system.
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor) -> Self {
+        self.interceptors = self.interceptors.with(interceptor);
+        self
+    }
+
+    /// Fails if the name is not a valid system name, see `Address::local`.
+    pub fn build(self) -> Result<ActorSystem> {
+        let mut system = ActorSystem::with_address(Address::local(&self.name)?);
+        system.default_ask_timeout = self.default_ask_timeout;
+        system.default_mailbox_size = self.default_mailbox_size;
+        system.spawner = self.spawner;
+        system.interceptors = self.interceptors;
+        Ok(system)
+    }
+}
+
+/// Asks an actor to stop and waits until it is gone. Returns its path if it
+/// cancelled the stop instead, which `forced` stops don't allow.
+async fn stop(
+    actor_ref: UntypedActorRef,
+    mut terminated: watch::Receiver<()>,
+    forced: bool,
+) -> Option<ActorPath> {
+    let (cancelled_tx, cancelled) = oneshot::channel();
+    let msg = SystemMessage::Stop {
+        cancelled: (!forced).then_some(cancelled_tx),
+    };
+    if actor_ref.send_system(msg).await.is_ok() && cancelled.await.is_ok() {
+        return Some(actor_ref.path().clone());
+    }
+
+    let _ = terminated.changed().await;
+    None
+}
+
+/// Notification delivered to `Actor::terminated` when a watched actor stops.
+#[derive(Clone, Debug)]
+pub struct Terminated {
+    pub path: ActorPath,
+    /// Incarnation of the stopped actor, see `ActorRef::uid`.
+    pub uid: u64,
+    pub reason: String,
 }
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub(crate) enum SystemMessage {
     Poison,
-    Watch { watcher: ActorPath },
-    Unwatch { watcher: ActorPath },
-    Terminated,
+    /// Like `Poison`, but `cancelled` is signalled if the actor cancels the
+    /// stop. Without it the stop can't be cancelled.
+    Stop {
+        cancelled: Option<oneshot::Sender<()>>,
+    },
+    Watch {
+        watcher: UntypedActorRef,
+    },
+    Unwatch {
+        watcher: UntypedActorRef,
+    },
+    Terminated(Terminated),
+    Failed {
+        child: UntypedActorRef,
+        error: Arc<ActorError>,
+        reply_to: oneshot::Sender<Directive>,
+    },
+    Restart,
+    /// Sent to the parent once a child is gone, so it forgets about it.
+    ChildTerminated(UntypedActorRef),
 }
 
 pub(crate) struct SystemMessageResponse;
@@ -137,18 +602,607 @@ impl<A: Actor> SystemHandler<SystemMessage> for A {
     async fn handle(
         &mut self,
         msg: SystemMessage,
-        _ctx: &mut ActorContext,
+        ctx: &mut ActorContext,
     ) -> (SystemMessageResponse, MessageHandlerResult) {
         match msg {
-            SystemMessage::Poison => (
-                SystemMessageResponse,
-                MessageHandlerResult::Stop {
-                    reason: "poisoned".into(),
+            SystemMessage::Poison => {
+                return (
+                    SystemMessageResponse,
+                    MessageHandlerResult::Stop {
+                        reason: "poisoned".into(),
+                    },
+                );
+            }
+            SystemMessage::Stop { cancelled } => {
+                match cancelled {
+                    Some(cancelled) => ctx.stop_requests.push(cancelled),
+                    None => ctx.stop_forced = true,
+                }
+                return (
+                    SystemMessageResponse,
+                    MessageHandlerResult::Stop {
+                        reason: "stopped".into(),
+                    },
+                );
+            }
+            SystemMessage::Watch { watcher } => {
+                ctx.watchers.insert(watcher);
+            }
+            SystemMessage::Unwatch { watcher } => {
+                ctx.watchers.remove(&watcher);
+            }
+            SystemMessage::Terminated(terminated) => {
+                // ignore notifications that raced with `unwatch`
+                if ctx
+                    .watching
+                    .remove(&(terminated.path.clone(), terminated.uid))
+                {
+                    self.terminated(ctx, terminated).await;
+                }
+            }
+            SystemMessage::Failed {
+                child,
+                error,
+                reply_to,
+            } => {
+                let directive = ctx.supervise(&child, &error).await;
+                let _ = reply_to.send(directive);
+                if directive == Directive::Escalate {
+                    let error = ActorError::ChildFailed(child.path().clone(), error);
+                    return (SystemMessageResponse, MessageHandlerResult::Failed(error));
+                }
+            }
+            SystemMessage::Restart => {
+                return (SystemMessageResponse, MessageHandlerResult::Restart);
+            }
+            SystemMessage::ChildTerminated(child) => {
+                ctx.restart_stats.remove(&child);
+            }
+        }
+
+        (SystemMessageResponse, MessageHandlerResult::None)
+    }
+
+    async fn reject(msg: SystemMessage, ctx: &mut ActorContext) {
+        // the watcher would wait forever otherwise
+        if let SystemMessage::Watch { watcher } = msg {
+            let terminated = Terminated {
+                path: ctx.path.clone(),
+                uid: ctx.myself.uid(),
+                reason: "stopped".into(),
+            };
+            let _ = watcher
+                .send_system(SystemMessage::Terminated(terminated))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorTask, StoppingResult, prelude::*};
+    use std::sync::atomic::AtomicUsize;
+    use tokio::{sync::mpsc, task::JoinHandle};
+
+    struct Target;
+    #[async_trait]
+    impl Actor for Target {}
+
+    struct Watcher {
+        terminated: mpsc::UnboundedSender<Terminated>,
+    }
+
+    #[async_trait]
+    impl Actor for Watcher {
+        async fn terminated(&mut self, _ctx: &mut ActorContext, msg: Terminated) {
+            self.terminated.send(msg).unwrap();
+        }
+    }
+
+    struct Watch(ActorRef<Target>);
+    impl Message for Watch {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Watch> for Watcher {
+        async fn handle(&mut self, msg: Watch, ctx: &mut ActorContext) {
+            ctx.watch(&msg.0).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn watch_delivers_terminated() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let target = system.spawn("target", || Target, 10).await.unwrap();
+        let watcher = system
+            .spawn(
+                "watcher",
+                move || Watcher {
+                    terminated: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+
+        watcher.ask(Watch(target.clone())).await.unwrap();
+        target.poison().await.unwrap();
+
+        let terminated = rx.recv().await.unwrap();
+        assert_eq!(terminated.path, *target.path());
+        assert_eq!(terminated.reason, "poisoned");
+    }
+
+    #[tokio::test]
+    async fn watch_dead_actor_delivers_terminated() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let target = system.spawn("target", || Target, 10).await.unwrap();
+        target.poison().await.unwrap();
+
+        let watcher = system
+            .spawn(
+                "watcher",
+                move || Watcher {
+                    terminated: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+        watcher.ask(Watch(target.clone())).await.unwrap();
+
+        let terminated = rx.recv().await.unwrap();
+        assert_eq!(terminated.path, *target.path());
+    }
+
+    struct Stubborn;
+
+    #[async_trait]
+    impl Actor for Stubborn {
+        async fn stopping(&mut self, _ctx: &mut ActorContext, _reason: &str) -> StoppingResult {
+            StoppingResult::Cancel
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct CountingSpawner(Arc<AtomicUsize>);
+
+    impl TaskSpawner for CountingSpawner {
+        fn spawn(&self, task: ActorTask) -> JoinHandle<()> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            tokio::spawn(task)
+        }
+    }
+
+    #[tokio::test]
+    async fn named_systems_are_isolated() {
+        let spawner = CountingSpawner::default();
+        let orders = ActorSystem::builder("orders")
+            .with_default_ask_timeout(Duration::from_millis(50))
+            .with_spawner(spawner.clone())
+            .build()
+            .unwrap();
+        let billing = ActorSystem::builder("billing").build().unwrap();
+
+        let props = orders.props(|| Target);
+        let a = orders.spawn_props("target", props).await.unwrap();
+        let b = billing.spawn("target", || Target, 10).await.unwrap();
+        assert_eq!(a.path().to_string(), "actor://orders/user/target");
+        assert_ne!(a.path(), b.path());
+        assert_eq!(a.ask_timeout, Some(Duration::from_millis(50)));
+        assert_eq!(b.ask_timeout, None);
+        assert_eq!(spawner.0.load(Ordering::Relaxed), 1);
+
+        orders.shutdown(Duration::from_secs(1)).await;
+        assert!(a.is_closed());
+        assert!(billing.get::<Target>(b.path()).await.is_some());
+
+        let invalid = ActorSystem::builder("no spaces").build();
+        assert!(matches!(invalid, Err(ActorError::InvalidPath(_))));
+    }
+
+    struct SpawnAnonymous;
+    impl Message for SpawnAnonymous {
+        type Response = ActorPath;
+    }
+
+    #[async_trait]
+    impl Handler<SpawnAnonymous> for Target {
+        async fn handle(&mut self, _msg: SpawnAnonymous, ctx: &mut ActorContext) -> ActorPath {
+            let child = ctx.spawn_anonymous(|| Target, 10).await.unwrap();
+            child.path().clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn anonymous_actors_get_unique_names() {
+        let system = ActorSystem::new();
+        let first = system.spawn_anonymous(|| Target, 10).await.unwrap();
+        let second = system.spawn_anonymous(|| Target, 10).await.unwrap();
+        assert_eq!(first.path().name(), "$0");
+        assert_eq!(second.path().name(), "$1");
+        assert_eq!(first.path().to_string().parse(), Ok(first.path().clone()));
+
+        // the generated name is skipped if it was taken by hand
+        system.spawn("session-3", || Target, 10).await.unwrap();
+        let session = system.spawn_with_prefix("session", || Target, 10);
+        assert_eq!(session.await.unwrap().path().name(), "session-2");
+        let session = system.spawn_with_prefix("session", || Target, 10);
+        assert_eq!(session.await.unwrap().path().name(), "session-4");
+
+        let child = first.ask(SpawnAnonymous).await.unwrap();
+        assert_eq!(child.parent().as_ref(), Some(first.path()));
+        assert!(system.spawn("$0", || Target, 10).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn respawn_is_a_new_incarnation() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let watcher = system
+            .spawn(
+                "watcher",
+                move || Watcher {
+                    terminated: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+
+        let old = system.spawn("target", || Target, 10).await.unwrap();
+        watcher.ask(Watch(old.clone())).await.unwrap();
+        system.stop_actor(old.path()).await.unwrap();
+        let new = system.spawn("target", || Target, 10).await.unwrap();
+
+        assert!(old != new);
+        assert!(system.get::<Target>(old.path()).await == Some(new.clone()));
+        assert!(old.untyped().poison().await.is_err());
+        assert!(!new.is_closed());
+        assert_eq!(rx.recv().await.unwrap().uid, old.uid());
+    }
+
+    #[tokio::test]
+    async fn shutdown_kills_stragglers() {
+        let system = ActorSystem::new();
+        let (tx, mut stopped) = mpsc::unbounded_channel();
+        let root = system
+            .spawn(
+                "root",
+                move || Node {
+                    stopped: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+        root.ask(Ping).await.unwrap();
+        let stubborn = system.spawn("stubborn", || Stubborn, 10).await.unwrap();
+        let stuck = system.spawn("stuck", || Target, 10).await.unwrap();
+
+        // watchers outside the system outlive the shutdown
+        let observer = ActorSystem::new();
+        let (tx, mut terminated) = mpsc::unbounded_channel();
+        let watcher = observer
+            .spawn(
+                "watcher",
+                move || Watcher {
+                    terminated: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+        watcher.ask(Watch(stuck.clone())).await.unwrap();
+        let (_unblock, blocked) = oneshot::channel();
+        stuck.tell(Block(blocked)).await.unwrap();
+        let mut events = system.subscribe_lifecycle();
+
+        let report = system.shutdown(Duration::from_millis(50)).await;
+        assert_eq!(report.killed, vec![stuck.path().clone()]);
+        assert!(stubborn.is_closed());
+        let mut paths = Vec::new();
+        while let Ok(path) = stopped.try_recv() {
+            paths.push(path);
+        }
+        let expected = ["root/child/child", "root/child", "root"].map(|path| path.parse().unwrap());
+        assert_eq!(paths, expected);
+
+        let killed = LifecycleEventKind::Stopped {
+            reason: "killed".into(),
+        };
+        let event = loop {
+            let event = events.recv().await.unwrap();
+            if event.path == *stuck.path() && event.kind == killed {
+                break event;
+            }
+        };
+        assert_eq!(event.actor_type, std::any::type_name::<Target>());
+        let terminated = terminated.recv().await.unwrap();
+        assert_eq!(
+            (terminated.uid, terminated.reason.as_str()),
+            (stuck.uid(), "killed")
+        );
+
+        let terminated = system.when_terminated().await;
+        assert_eq!(terminated.killed, report.killed);
+        assert!(system.spawn("late", || Target, 10).await.is_err());
+    }
+
+    struct Node {
+        stopped: mpsc::UnboundedSender<ActorPath>,
+    }
+
+    #[async_trait]
+    impl Actor for Node {
+        async fn started(&mut self, ctx: &mut ActorContext) -> Result<()> {
+            if ctx.path.segments().len() < 3 {
+                let stopped = self.stopped.clone();
+                ctx.spawn(
+                    "child",
+                    move || Node {
+                        stopped: stopped.clone(),
+                    },
+                    10,
+                )
+                .await?;
+            }
+            Ok(())
+        }
+
+        async fn stopped(&mut self, ctx: &mut ActorContext) {
+            self.stopped.send(ctx.path.clone()).unwrap();
+        }
+    }
+
+    struct Ping;
+    impl Message for Ping {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Ping> for Node {
+        async fn handle(&mut self, _msg: Ping, _ctx: &mut ActorContext) {}
+    }
+
+    struct Quit;
+    impl Message for Quit {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Quit> for Node {
+        async fn handle(&mut self, _msg: Quit, ctx: &mut ActorContext) {
+            ctx.stop_self().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn stop_actor_stops_subtree_children_first() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let root = system
+            .spawn(
+                "root",
+                move || Node {
+                    stopped: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+        root.ask(Ping).await.unwrap();
+        let child = system
+            .get::<Node>(&root.path().child("child").unwrap())
+            .await;
+        child.unwrap().ask(Ping).await.unwrap();
+
+        system.stop_actor(root.path()).await.unwrap();
+
+        let mut stopped = Vec::new();
+        while let Ok(path) = rx.try_recv() {
+            stopped.push(path);
+        }
+        let expected = ["root/child/child", "root/child", "root"].map(|path| path.parse().unwrap());
+        assert_eq!(stopped, expected);
+        assert!(root.tell(Ping).await.is_err());
+        assert!(system.actors.read().await.top_level().is_empty());
+    }
+
+    #[tokio::test]
+    async fn actors_can_stop_themselves() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let root = system
+            .spawn(
+                "root",
+                move || Node {
+                    stopped: tx.clone(),
                 },
-            ),
-            SystemMessage::Watch { watcher: _ } => todo!(),
-            SystemMessage::Terminated => todo!(),
-            SystemMessage::Unwatch { watcher: _ } => todo!(),
+                10,
+            )
+            .await
+            .unwrap();
+        root.ask(Ping).await.unwrap();
+        let child = system
+            .get::<Node>(&root.path().child("child").unwrap())
+            .await;
+        child.unwrap().ask(Quit).await.unwrap();
+
+        let expected = ["root/child/child", "root/child"].map(|path| path.parse().unwrap());
+        assert_eq!(rx.recv().await.unwrap(), expected[0]);
+        assert_eq!(rx.recv().await.unwrap(), expected[1]);
+        root.ask(Ping).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stop_actor_honours_cancel() {
+        let system = ActorSystem::new();
+        let stubborn = system.spawn("stubborn", || Stubborn, 10).await.unwrap();
+
+        let result = system.stop_actor(stubborn.path()).await;
+        assert!(matches!(result, Err(ActorError::StopCancelled(path)) if path == *stubborn.path()));
+        assert!(!stubborn.is_closed());
+    }
+
+    #[tokio::test]
+    async fn spawn_checks_parent() {
+        let system = ActorSystem::new();
+        let props = || {
+            ActorProps::new(
+                || Target,
+                || Box::new(DefaultActorSpawner::new()),
+                || Box::new(DefaultMailbox::new(10)),
+            )
+        };
+
+        let orphan = system
+            .spawn_path("missing/child".parse().unwrap(), props())
+            .await;
+        assert!(
+            matches!(orphan, Err(ActorError::ParentNotFound(path)) if path == "missing".parse().unwrap())
+        );
+
+        let parent = system
+            .spawn_props("parent", props().with_max_children(1))
+            .await
+            .unwrap();
+        let first = system
+            .spawn_path(parent.path().child("a").unwrap(), props())
+            .await;
+        let second = system
+            .spawn_path(parent.path().child("b").unwrap(), props())
+            .await;
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(ActorError::TooManyChildren(path)) if path == *parent.path()));
+    }
+
+    struct Hire;
+    impl Message for Hire {
+        type Response = (ActorRef<Worker>, ActorRef<Stubborn>);
+    }
+
+    #[async_trait]
+    impl Handler<Hire> for Watcher {
+        async fn handle(
+            &mut self,
+            _msg: Hire,
+            ctx: &mut ActorContext,
+        ) -> (ActorRef<Worker>, ActorRef<Stubborn>) {
+            let worker = ctx.spawn("worker", || Worker, 10).await.unwrap();
+            let stubborn = ctx.spawn("stubborn", || Stubborn, 10).await.unwrap();
+            (worker, stubborn)
+        }
+    }
+
+    struct Fire;
+    impl Message for Fire {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Fire> for Watcher {
+        async fn handle(&mut self, _msg: Fire, ctx: &mut ActorContext) {
+            ctx.stop("worker").await.unwrap();
+        }
+    }
+
+    struct Worker;
+    #[async_trait]
+    impl Actor for Worker {}
+
+    struct Block(oneshot::Receiver<()>);
+    impl Message for Block {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Block> for Worker {
+        async fn handle(&mut self, msg: Block, _ctx: &mut ActorContext) {
+            let _ = msg.0.await;
+        }
+    }
+
+    #[async_trait]
+    impl Handler<Block> for Target {
+        async fn handle(&mut self, msg: Block, _ctx: &mut ActorContext) {
+            let _ = msg.0.await;
+        }
+    }
+
+    struct Crash;
+    impl Message for Crash {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Crash> for Worker {
+        async fn handle(&mut self, _msg: Crash, _ctx: &mut ActorContext) {
+            panic!("crash");
+        }
+    }
+
+    #[tokio::test]
+    async fn stopping_a_child_does_not_block_the_parent() {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let foreman = system
+            .spawn(
+                "foreman",
+                move || Watcher {
+                    terminated: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+        let (worker, _) = foreman.ask(Hire).await.unwrap();
+
+        // the worker fails while the stop is queued, which needs the foreman
+        let (release, blocked) = oneshot::channel();
+        worker.tell(Block(blocked)).await.unwrap();
+        worker.tell(Crash).await.unwrap();
+        let fire = tokio::time::timeout(Duration::from_secs(5), foreman.ask(Fire));
+        fire.await.unwrap().unwrap();
+        release.send(()).unwrap();
+
+        assert_eq!(rx.recv().await.unwrap().path, *worker.path());
+        assert!(worker.is_closed());
+    }
+
+    #[tokio::test]
+    async fn children_cannot_cancel_the_stop_of_their_parent() {
+        let system = ActorSystem::new();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let foreman = system
+            .spawn(
+                "foreman",
+                move || Watcher {
+                    terminated: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+        let (_, stubborn) = foreman.ask(Hire).await.unwrap();
+
+        let mut events = system.subscribe_lifecycle();
+        foreman.poison().await.unwrap();
+        loop {
+            let event = events.recv().await.unwrap();
+            if event.path == *foreman.path()
+                && matches!(event.kind, LifecycleEventKind::Stopped { .. })
+            {
+                break;
+            }
         }
+        assert!(stubborn.is_closed());
+        assert!(system.get::<Stubborn>(stubborn.path()).await.is_none());
     }
 }
@@ -0,0 +1,275 @@
+use async_trait::async_trait;
+use std::{collections::HashMap, time::Duration};
+use tokio::{
+    task::AbortHandle,
+    time::{Instant, MissedTickBehavior},
+};
+
+use crate::{
+    ActorContext, ActorError, ActorRef, Handler, Message, MessageHandler, MessageHandlerResult,
+    Priority, handler::Envelope,
+};
+
+/// How a periodic timer behaves when the actor can't keep up with it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimerMode {
+    /// Ticks are scheduled relative to the start of the timer, late ticks are
+    /// sent in a burst to catch up.
+    FixedRate,
+    /// Every tick is scheduled `interval` after the previous one was sent.
+    FixedDelay,
+}
+
+#[derive(Debug)]
+struct Timer {
+    generation: u64,
+    abort_handle: AbortHandle,
+}
+
+/// Timers of one actor. Every timer gets a new generation, so messages of a
+/// cancelled or replaced timer still sitting in the mailbox are dropped.
+#[derive(Debug, Default)]
+pub(crate) struct Timers {
+    next_generation: u64,
+    active: HashMap<String, Timer>,
+}
+
+impl Timers {
+    pub(crate) fn start_single<A, M>(
+        &mut self,
+        key: String,
+        actor_ref: ActorRef<A>,
+        msg: M,
+        delay: Duration,
+    ) where
+        A: Handler<M>,
+        M: Message,
+    {
+        let generation = self.next_generation();
+        let envelope = TimerEnvelope::new(key.clone(), generation, true, msg);
+        let task = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = actor_ref.send(Box::new(envelope)).await;
+        });
+
+        self.insert(key, generation, task.abort_handle());
+    }
+
+    pub(crate) fn start_periodic<A, M>(
+        &mut self,
+        key: String,
+        actor_ref: ActorRef<A>,
+        msg: M,
+        interval: Duration,
+        mode: TimerMode,
+    ) where
+        A: Handler<M>,
+        M: Message + Clone,
+    {
+        let generation = self.next_generation();
+        let timer_key = key.clone();
+        let task = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval_at(Instant::now() + interval, interval);
+            ticks.set_missed_tick_behavior(match mode {
+                TimerMode::FixedRate => MissedTickBehavior::Burst,
+                TimerMode::FixedDelay => MissedTickBehavior::Delay,
+            });
+
+            loop {
+                ticks.tick().await;
+                let envelope =
+                    TimerEnvelope::new(timer_key.clone(), generation, false, msg.clone());
+                if actor_ref.send(Box::new(envelope)).await.is_err() {
+                    break;
+                }
+                if mode == TimerMode::FixedDelay {
+                    ticks.reset();
+                }
+            }
+        });
+
+        self.insert(key, generation, task.abort_handle());
+    }
+
+    pub(crate) fn cancel(&mut self, key: &str) {
+        if let Some(timer) = self.active.remove(key) {
+            timer.abort_handle.abort();
+        }
+    }
+
+    pub(crate) fn cancel_all(&mut self) {
+        for (_, timer) in self.active.drain() {
+            timer.abort_handle.abort();
+        }
+    }
+
+    pub(crate) fn is_active(&self, key: &str) -> bool {
+        self.active.contains_key(key)
+    }
+
+    fn next_generation(&mut self) -> u64 {
+        self.next_generation += 1;
+        self.next_generation
+    }
+
+    fn insert(&mut self, key: String, generation: u64, abort_handle: AbortHandle) {
+        let timer = Timer {
+            generation,
+            abort_handle,
+        };
+        if let Some(previous) = self.active.insert(key, timer) {
+            previous.abort_handle.abort();
+        }
+    }
+
+    fn is_current(&self, key: &str, generation: u64) -> bool {
+        self.active
+            .get(key)
+            .is_some_and(|timer| timer.generation == generation)
+    }
+}
+
+struct TimerEnvelope<M, A>
+where
+    M: Message,
+    A: Handler<M>,
+{
+    key: String,
+    generation: u64,
+    single: bool,
+    /// Set once the timer was found current, so an unstashed message isn't dropped.
+    checked: bool,
+    envelope: Envelope<M, A>,
+}
+
+impl<M, A> TimerEnvelope<M, A>
+where
+    M: Message,
+    A: Handler<M>,
+{
+    fn new(key: String, generation: u64, single: bool, msg: M) -> Self {
+        Self {
+            key,
+            generation,
+            single,
+            checked: false,
+            envelope: Envelope::new(msg, None),
+        }
+    }
+}
+
+#[async_trait]
+impl<M, A> MessageHandler<A> for TimerEnvelope<M, A>
+where
+    M: Message,
+    A: Handler<M>,
+{
+    async fn handle(&mut self, actor: &mut A, ctx: &mut ActorContext) -> MessageHandlerResult {
+        if !self.checked {
+            if !ctx.timers.is_current(&self.key, self.generation) {
+                return MessageHandlerResult::None;
+            }
+            if self.single {
+                ctx.timers.active.remove(&self.key);
+            }
+            self.checked = true;
+        }
+
+        self.envelope.handle(actor, ctx).await
+    }
+
+    fn message_type(&self) -> &'static str {
+        self.envelope.message_type()
+    }
+
+    fn priority(&self) -> Priority {
+        self.envelope.priority()
+    }
+
+    fn fail(&mut self, error: ActorError) {
+        self.envelope.fail(error);
+    }
+
+    async fn reject(&mut self, ctx: &mut ActorContext) {
+        // only stashed ticks were delivered, the timers are gone already
+        if self.checked {
+            self.envelope.reject(ctx).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use tokio::sync::mpsc;
+
+    struct Ticker {
+        mode: TimerMode,
+        ticks: mpsc::UnboundedSender<&'static str>,
+    }
+
+    #[async_trait]
+    impl Actor for Ticker {
+        async fn started(&mut self, ctx: &mut ActorContext) -> Result<()> {
+            let delay = Duration::from_millis(10);
+            let once = Duration::from_millis(25);
+            ctx.start_single_timer::<Self, _>("once", Tick("once"), once);
+            ctx.start_single_timer::<Self, _>("cancelled", Tick("cancelled"), delay);
+            ctx.start_periodic_timer::<Self, _>("every", Tick("every"), delay, self.mode);
+            ctx.cancel_timer("cancelled");
+            Ok(())
+        }
+    }
+
+    #[derive(Clone)]
+    struct Tick(&'static str);
+    impl Message for Tick {
+        type Response = ();
+    }
+
+    #[async_trait]
+    impl Handler<Tick> for Ticker {
+        async fn handle(&mut self, msg: Tick, ctx: &mut ActorContext) {
+            self.ticks.send(msg.0).unwrap();
+            if msg.0 == "every" && !ctx.is_timer_active("once") {
+                ctx.cancel_timer("every");
+            }
+        }
+    }
+
+    async fn ticks(mode: TimerMode) -> Vec<&'static str> {
+        let system = ActorSystem::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        system
+            .spawn(
+                "ticker",
+                move || Ticker {
+                    mode,
+                    ticks: tx.clone(),
+                },
+                10,
+            )
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut ticks = Vec::new();
+        while let Ok(tick) = rx.try_recv() {
+            ticks.push(tick);
+        }
+        ticks
+    }
+
+    // paused time advances only once every task is idle, so the ticks are
+    // handled before the sleep ends however slow the machine is. "every"
+    // ticks at 10, 20 and 30ms and is cancelled by the first tick after
+    // "once" fired at 25ms.
+    #[tokio::test(start_paused = true)]
+    async fn timers_deliver_to_own_mailbox() {
+        let expected = ["every", "every", "once", "every"];
+        assert_eq!(ticks(TimerMode::FixedRate).await, expected);
+        assert_eq!(ticks(TimerMode::FixedDelay).await, expected);
+    }
+}